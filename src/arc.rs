@@ -2,14 +2,20 @@
 use super::FallibleBox;
 use super::TryClone;
 
+use crate::boxed::alloc;
 use crate::TryReserveError;
+use alloc::alloc::{dealloc, Layout};
 use alloc::boxed::Box;
 use alloc::sync::Arc;
+use core::marker::PhantomData;
+use core::ops::Deref;
+use core::ptr::NonNull;
+use core::sync::atomic::{self, AtomicUsize, Ordering};
 
 /// trait to implement Fallible Arc
 #[deprecated(
-	since = "0.3.1",
-	note = "⚠️️️this function is not completely fallible, it can panic !, see [issue](https://github.com/vcombey/fallible_collections/issues/13). help wanted"
+    since = "0.3.1",
+    note = "⚠️️️this function is not completely fallible, it can panic !, see [issue](https://github.com/vcombey/fallible_collections/issues/13). use TryArc instead"
 )]
 pub trait FallibleArc<T> {
     /// try creating a new Arc, returning a Result<Box<T>,
@@ -36,8 +42,178 @@ impl<T: ?Sized> TryClone for Arc<T> {
     }
 }
 
+/// Past this many references the counter could overflow, see std::sync::Arc
+const MAX_REFCOUNT: usize = isize::MAX as usize;
+
+struct ArcInner<T> {
+    strong: AtomicUsize,
+    // all the strong pointers together hold one implicit weak reference,
+    // see std::sync::Arc. There is no weak pointer to TryArc yet, so it
+    // stays at 1 until the last TryArc is dropped.
+    weak: AtomicUsize,
+    data: T,
+}
+
+/// TryArc is a thread-safe reference-counting pointer whose reference
+/// counts and value live in a single block, allocated fallibly by
+/// `try_new`.
+///
+/// Unlike `FallibleArc`, there is no second allocation hidden behind
+/// the conversion from a `Box`. Cloning a `TryArc` only increments the
+/// reference count, so it never allocates.
+pub struct TryArc<T> {
+    ptr: NonNull<ArcInner<T>>,
+    phantom: PhantomData<ArcInner<T>>,
+}
+
+unsafe impl<T: Sync + Send> Send for TryArc<T> {}
+unsafe impl<T: Sync + Send> Sync for TryArc<T> {}
+
+impl<T> TryArc<T> {
+    /// try creating a new TryArc, returning an error if the allocation of
+    /// the reference count and the value failed
+    pub fn try_new(data: T) -> Result<Self, TryReserveError> {
+        let layout = Layout::new::<ArcInner<T>>();
+        let ptr = alloc(layout)?.cast::<ArcInner<T>>();
+        unsafe {
+            core::ptr::write(
+                ptr.as_ptr(),
+                ArcInner {
+                    strong: AtomicUsize::new(1),
+                    weak: AtomicUsize::new(1),
+                    data,
+                },
+            );
+        }
+        Ok(Self {
+            ptr,
+            phantom: PhantomData,
+        })
+    }
+
+    /// Returns the inner value if `this` is the only reference to it,
+    /// otherwise gives `this` back.
+    pub fn try_unwrap(this: Self) -> Result<T, Self> {
+        if this
+            .inner()
+            .strong
+            .compare_exchange(1, 0, Ordering::Relaxed, Ordering::Relaxed)
+            .is_err()
+        {
+            return Err(this);
+        }
+        atomic::fence(Ordering::Acquire);
+
+        unsafe {
+            let data = core::ptr::read(&this.inner().data);
+            this.release_weak();
+            core::mem::forget(this);
+            Ok(data)
+        }
+    }
+
+    /// Returns a mutable reference to the inner value if there are no
+    /// other `TryArc` pointing to it.
+    #[inline]
+    pub fn get_mut(this: &mut Self) -> Option<&mut T> {
+        if this.inner().strong.load(Ordering::Acquire) == 1 {
+            // we are the only reference, nobody else can observe the value
+            unsafe { Some(&mut (*this.ptr.as_ptr()).data) }
+        } else {
+            None
+        }
+    }
+
+    /// Gets the number of `TryArc` pointing to this allocation.
+    #[inline]
+    pub fn strong_count(this: &Self) -> usize {
+        this.inner().strong.load(Ordering::SeqCst)
+    }
+
+    /// Returns true if the two `TryArc` point to the same allocation.
+    #[inline]
+    pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+        this.ptr == other.ptr
+    }
+
+    #[inline(always)]
+    fn inner(&self) -> &ArcInner<T> {
+        unsafe { self.ptr.as_ref() }
+    }
+
+    /// Drops the implicit weak reference of the strong pointers once the
+    /// value is gone, freeing the block if it was the last one.
+    ///
+    /// # Safety
+    /// the strong count must have reached 0 and the value been dropped or
+    /// moved out
+    unsafe fn release_weak(&self) {
+        if self.inner().weak.fetch_sub(1, Ordering::Release) == 1 {
+            atomic::fence(Ordering::Acquire);
+            dealloc(self.ptr.as_ptr().cast(), Layout::new::<ArcInner<T>>());
+        }
+    }
+}
+
+impl<T> Clone for TryArc<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        let old_size = self.inner().strong.fetch_add(1, Ordering::Relaxed);
+        if old_size > MAX_REFCOUNT {
+            self.inner().strong.fetch_sub(1, Ordering::Relaxed);
+            panic!("TryArc reference count overflow");
+        }
+        Self {
+            ptr: self.ptr,
+            phantom: PhantomData,
+        }
+    }
+}
+
+/// Just a TryClone boilerplate for TryArc
+impl<T> TryClone for TryArc<T> {
+    #[inline(always)]
+    fn try_clone(&self) -> Result<Self, TryReserveError> {
+        Ok(self.clone())
+    }
+}
+
+impl<T> Drop for TryArc<T> {
+    fn drop(&mut self) {
+        if self.inner().strong.fetch_sub(1, Ordering::Release) != 1 {
+            return;
+        }
+        // synchronize with the other threads which dropped their reference,
+        // see std::sync::Arc
+        atomic::fence(Ordering::Acquire);
+
+        unsafe {
+            core::ptr::drop_in_place(&mut (*self.ptr.as_ptr()).data);
+            self.release_weak();
+        }
+    }
+}
+
+impl<T> Deref for TryArc<T> {
+    type Target = T;
+
+    #[inline(always)]
+    fn deref(&self) -> &T {
+        &self.inner().data
+    }
+}
+
+impl<T: core::fmt::Debug> core::fmt::Debug for TryArc<T> {
+    #[inline]
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
 #[cfg(test)]
 mod test {
+    use super::*;
+
     #[test]
     fn fallible_rc() {
         use std::sync::Arc;
@@ -49,4 +225,47 @@ mod test {
         let _y = Arc::clone(&x);
         assert!(Arc::get_mut(&mut x).is_none());
     }
+
+    #[test]
+    fn tryarc() {
+        let mut x = TryArc::try_new(3).unwrap();
+        *TryArc::get_mut(&mut x).unwrap() = 4;
+        assert_eq!(*x, 4);
+
+        let y = x.clone();
+        assert_eq!(TryArc::strong_count(&x), 2);
+        assert!(TryArc::ptr_eq(&x, &y));
+        assert!(TryArc::get_mut(&mut x).is_none());
+
+        let x = TryArc::try_unwrap(x).unwrap_err();
+        drop(y);
+        assert_eq!(TryArc::strong_count(&x), 1);
+        assert_eq!(TryArc::try_unwrap(x).unwrap(), 4);
+    }
+
+    #[test]
+    fn tryarc_drop() {
+        let value = std::rc::Rc::new(());
+        let x = TryArc::try_new(value.clone()).unwrap();
+        let y = x.try_clone().unwrap();
+        drop(x);
+        assert_eq!(std::rc::Rc::strong_count(&value), 2);
+        drop(y);
+        assert_eq!(std::rc::Rc::strong_count(&value), 1);
+    }
+
+    #[test]
+    fn tryarc_threads() {
+        let x = TryArc::try_new(42).unwrap();
+        let handles: std::vec::Vec<_> = (0..4)
+            .map(|_| {
+                let x = x.clone();
+                std::thread::spawn(move || *x)
+            })
+            .collect();
+        for handle in handles {
+            assert_eq!(handle.join().unwrap(), 42);
+        }
+        assert_eq!(TryArc::strong_count(&x), 1);
+    }
 }
@@ -1,8 +1,10 @@
 //! Implement Fallible Box
 use super::TryClone;
+use crate::TryFromIterator;
 use crate::TryReserveError;
 use alloc::alloc::Layout;
 use alloc::boxed::Box;
+use alloc::vec::Vec;
 use core::borrow::Borrow;
 use core::ops::Deref;
 use core::ptr::NonNull;
@@ -63,16 +65,25 @@ impl<T> Deref for TryBox<T> {
     }
 }
 
-fn alloc(layout: Layout) -> Result<NonNull<u8>, TryReserveError> {
+pub(crate) fn alloc(layout: Layout) -> Result<NonNull<u8>, TryReserveError> {
     #[cfg(feature = "unstable")] // requires allocator_api
     {
         use core::alloc::Allocator;
         alloc::alloc::Global
             .allocate(layout)
-            .map_err(|_e| TryReserveError::AllocError {
-                layout,
+            .map_err(|_e| {
+                #[cfg(feature = "rust_1_57")]
+                {
+                    TryReserveError::AllocError { layout }
+                }
                 #[cfg(not(feature = "rust_1_57"))]
-                non_exhaustive: (),
+                {
+                    alloc::collections::TryReserveErrorKind::AllocError {
+                        layout,
+                        non_exhaustive: (),
+                    }
+                    .into()
+                }
             })
             .map(|v| v.cast())
     }
@@ -84,7 +95,7 @@ fn alloc(layout: Layout) -> Result<NonNull<u8>, TryReserveError> {
                 // See https://doc.rust-lang.org/stable/std/alloc/trait.GlobalAlloc.html#safety-1
                 Ok(NonNull::dangling())
             }
-            1..=core::usize::MAX => {
+            1..=usize::MAX => {
                 let ptr = unsafe { alloc::alloc::alloc(layout) };
                 core::ptr::NonNull::new(ptr).ok_or(TryReserveError::AllocError { layout })
             }
@@ -104,6 +115,26 @@ impl<T> FallibleBox<T> for Box<T> {
     }
 }
 
+impl<I> TryFromIterator<I> for Box<[I]> {
+    fn try_from_iterator<T: IntoIterator<Item = I>>(iterator: T) -> Result<Self, TryReserveError> {
+        let mut v = Vec::try_from_iterator(iterator)?;
+        let len = v.len();
+        let layout = Layout::array::<I>(len).expect("the vec already holds this layout");
+        // into_boxed_slice only reallocates to drop the spare capacity, and
+        // shrinking to an empty layout just frees the buffer
+        if len == v.capacity() || layout.size() == 0 {
+            return Ok(v.into_boxed_slice());
+        }
+        let ptr = alloc(layout)?.cast::<I>().as_ptr();
+        unsafe {
+            core::ptr::copy_nonoverlapping(v.as_ptr(), ptr, len);
+            // the elements were moved to the box, only free the buffer
+            v.set_len(0);
+            Ok(Box::from_raw(core::ptr::slice_from_raw_parts_mut(ptr, len)))
+        }
+    }
+}
+
 impl<T: TryClone> TryClone for Box<T> {
     #[inline]
     fn try_clone(&self) -> Result<Self, TryReserveError> {
@@ -121,6 +152,36 @@ mod tests {
         *v = 3;
         assert_eq!(*v, 3);
     }
+    /// yields nothing while claiming a lower bound of 4
+    struct Liar;
+
+    impl Iterator for Liar {
+        type Item = u64;
+        fn next(&mut self) -> Option<u64> {
+            None
+        }
+        fn size_hint(&self) -> (usize, Option<usize>) {
+            (4, None)
+        }
+    }
+
+    #[test]
+    fn try_from_iterator_empty_with_spare_capacity() {
+        let b: Box<[u64]> = TryFromIterator::try_from_iterator(Liar).unwrap();
+        assert!(b.is_empty());
+        assert_eq!(b.as_ptr() as usize % core::mem::align_of::<u64>(), 0);
+    }
+
+    #[test]
+    fn try_from_iterator_aligned_zst() {
+        #[repr(align(16))]
+        struct Zst;
+
+        let b: Box<[Zst]> = TryFromIterator::try_from_iterator((0..3).map(|_| Zst)).unwrap();
+        assert_eq!(b.len(), 3);
+        assert_eq!(b.as_ptr() as usize % 16, 0);
+    }
+
     // #[test]
     // fn big_alloc() {
     //     let layout = Layout::from_size_align(1_000_000_000_000, 8).unwrap();
@@ -148,4 +209,22 @@ mod tests {
             Some(TryBox::try_new(NonCopyType).unwrap());
         let _ref_option: Option<&NonCopyType> = try_box_option.as_deref();
     }
+
+    #[test]
+    fn try_collect_boxed_slice() {
+        use crate::TryCollect;
+
+        let b: Box<[u32]> = (0..10).try_collect().unwrap();
+        assert_eq!(&*b, &(0..10).collect::<std::vec::Vec<_>>()[..]);
+        let b: Box<[u32]> = (0..10).filter(|i| i % 3 == 0).try_collect().unwrap();
+        assert_eq!(&*b, &[0, 3, 6, 9]);
+        let b: Box<[()]> = (0..5).map(|_| ()).try_collect().unwrap();
+        assert_eq!(b.len(), 5);
+        let b: Box<[std::string::String]> = vec!["a".into(), "b".into()]
+            .into_iter()
+            .filter(|_| true)
+            .try_collect()
+            .unwrap();
+        assert_eq!(&*b, &["a", "b"]);
+    }
 }
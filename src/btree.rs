@@ -1,5 +1,7 @@
 //! Implement Fallible Btree, As there is no try_reserve methods on btree, I add no choice but to fork the std implementation and change return types.
-//! Currently this functionality is only available when building this crate with nightly and the `unstable` feature.
+//! The fork only relies on stable features, so it is available on every toolchain.
+#![allow(clippy::type_complexity)]
+
 pub mod map;
 pub use map::BTreeMap;
 
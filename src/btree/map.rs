@@ -1,15 +1,16 @@
 use crate::TryReserveError;
+use crate::{TryExtend, TryFromIterator};
 use core::borrow::Borrow;
 use core::cmp::Ordering;
 use core::fmt::Debug;
 use core::hash::{Hash, Hasher};
-use core::iter::{FromIterator, FusedIterator, Peekable};
+use core::iter::{FusedIterator, Peekable};
 use core::marker::PhantomData;
 use core::ops::Bound::{Excluded, Included, Unbounded};
 use core::ops::{Index, RangeBounds};
-use core::{fmt, intrinsics, mem, ptr};
+use core::{fmt, hint, mem, ptr};
 
-use super::node::{self, marker, ForceResult::*, Handle, InsertResult::*, NodeRef};
+use super::node::{self, marker, ForceResult::*, Handle, InsertResult::*, NodePool, NodeRef};
 use super::search::{self, SearchResult::*};
 
 use Entry::*;
@@ -120,13 +121,12 @@ use UnderflowResult::*;
 /// let stat = player_stats.entry("attack").or_insert(100);
 /// *stat += random_stat_buff();
 /// ```
-
 pub struct BTreeMap<K, V> {
     root: node::Root<K, V>,
     length: usize,
 }
 
-unsafe impl<#[may_dangle] K, #[may_dangle] V> Drop for BTreeMap<K, V> {
+impl<K, V> Drop for BTreeMap<K, V> {
     fn drop(&mut self) {
         unsafe {
             drop(ptr::read(self).into_iter());
@@ -138,12 +138,12 @@ use crate::TryClone;
 
 impl<K: TryClone, V: TryClone> TryClone for BTreeMap<K, V> {
     fn try_clone(&self) -> Result<BTreeMap<K, V>, TryReserveError> {
-        fn clone_subtree<'a, K: TryClone, V: TryClone>(
+        fn clone_subtree<'a, K, V>(
             node: node::NodeRef<marker::Immut<'a>, K, V, marker::LeafOrInternal>,
         ) -> Result<BTreeMap<K, V>, TryReserveError>
         where
-            K: 'a,
-            V: 'a,
+            K: 'a + TryClone,
+            V: 'a + TryClone,
         {
             match node.force() {
                 Leaf(leaf) => {
@@ -203,7 +203,7 @@ impl<K: TryClone, V: TryClone> TryClone for BTreeMap<K, V> {
             }
         }
 
-        if self.len() == 0 {
+        if self.is_empty() {
             // Ideally we'd call `BTreeMap::new` here, but that has the `K:
             // Ord` constraint, which this method lacks.
             Ok(BTreeMap {
@@ -216,86 +216,6 @@ impl<K: TryClone, V: TryClone> TryClone for BTreeMap<K, V> {
     }
 }
 
-impl<K: Clone, V: Clone> Clone for BTreeMap<K, V> {
-    fn clone(&self) -> BTreeMap<K, V> {
-        fn clone_subtree<'a, K: Clone, V: Clone>(
-            node: node::NodeRef<marker::Immut<'a>, K, V, marker::LeafOrInternal>,
-        ) -> BTreeMap<K, V>
-        where
-            K: 'a,
-            V: 'a,
-        {
-            match node.force() {
-                Leaf(leaf) => {
-                    let mut out_tree = BTreeMap {
-                        root: node::Root::new_leaf().expect("Out of Mem"),
-                        length: 0,
-                    };
-
-                    {
-                        let mut out_node = match out_tree.root.as_mut().force() {
-                            Leaf(leaf) => leaf,
-                            Internal(_) => unreachable!(),
-                        };
-
-                        let mut in_edge = leaf.first_edge();
-                        while let Ok(kv) = in_edge.right_kv() {
-                            let (k, v) = kv.into_kv();
-                            in_edge = kv.right_edge();
-
-                            out_node.push(k.clone(), v.clone());
-                            out_tree.length += 1;
-                        }
-                    }
-
-                    out_tree
-                }
-                Internal(internal) => {
-                    let mut out_tree = clone_subtree(internal.first_edge().descend());
-
-                    {
-                        let mut out_node = out_tree.root.push_level().expect("Out of Mem");
-                        let mut in_edge = internal.first_edge();
-                        while let Ok(kv) = in_edge.right_kv() {
-                            let (k, v) = kv.into_kv();
-                            in_edge = kv.right_edge();
-
-                            let k = (*k).clone();
-                            let v = (*v).clone();
-                            let subtree = clone_subtree(in_edge.descend());
-
-                            // We can't destructure subtree directly
-                            // because BTreeMap implements Drop
-                            let (subroot, sublength) = unsafe {
-                                let root = ptr::read(&subtree.root);
-                                let length = subtree.length;
-                                mem::forget(subtree);
-                                (root, length)
-                            };
-
-                            out_node.push(k, v, subroot);
-                            out_tree.length += 1 + sublength;
-                        }
-                    }
-
-                    out_tree
-                }
-            }
-        }
-
-        if self.len() == 0 {
-            // Ideally we'd call `BTreeMap::new` here, but that has the `K:
-            // Ord` constraint, which this method lacks.
-            BTreeMap {
-                root: node::Root::shared_empty_root(),
-                length: 0,
-            }
-        } else {
-            clone_subtree(self.root.as_ref())
-        }
-    }
-}
-
 impl<K, Q: ?Sized> super::Recover<Q> for BTreeMap<K, ()>
 where
     K: Borrow<Q> + Ord,
@@ -350,7 +270,6 @@ where
 ///
 /// [`iter`]: struct.BTreeMap.html#method.iter
 /// [`BTreeMap`]: struct.BTreeMap.html
-
 pub struct Iter<'a, K: 'a, V: 'a> {
     range: Range<'a, K, V>,
     length: usize,
@@ -369,7 +288,6 @@ impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for Iter<'_, K, V> {
 ///
 /// [`iter_mut`]: struct.BTreeMap.html#method.iter_mut
 /// [`BTreeMap`]: struct.BTreeMap.html
-
 #[derive(Debug)]
 pub struct IterMut<'a, K: 'a, V: 'a> {
     range: RangeMut<'a, K, V>,
@@ -383,7 +301,6 @@ pub struct IterMut<'a, K: 'a, V: 'a> {
 ///
 /// [`into_iter`]: struct.BTreeMap.html#method.into_iter
 /// [`BTreeMap`]: struct.BTreeMap.html
-
 pub struct IntoIter<K, V> {
     front: Handle<NodeRef<marker::Owned, K, V, marker::Leaf>, marker::Edge>,
     back: Handle<NodeRef<marker::Owned, K, V, marker::Leaf>, marker::Edge>,
@@ -407,7 +324,6 @@ impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for IntoIter<K, V> {
 ///
 /// [`keys`]: struct.BTreeMap.html#method.keys
 /// [`BTreeMap`]: struct.BTreeMap.html
-
 pub struct Keys<'a, K: 'a, V: 'a> {
     inner: Iter<'a, K, V>,
 }
@@ -425,7 +341,6 @@ impl<K: fmt::Debug, V> fmt::Debug for Keys<'_, K, V> {
 ///
 /// [`values`]: struct.BTreeMap.html#method.values
 /// [`BTreeMap`]: struct.BTreeMap.html
-
 pub struct Values<'a, K: 'a, V: 'a> {
     inner: Iter<'a, K, V>,
 }
@@ -443,7 +358,6 @@ impl<K, V: fmt::Debug> fmt::Debug for Values<'_, K, V> {
 ///
 /// [`values_mut`]: struct.BTreeMap.html#method.values_mut
 /// [`BTreeMap`]: struct.BTreeMap.html
-
 #[derive(Debug)]
 pub struct ValuesMut<'a, K: 'a, V: 'a> {
     inner: IterMut<'a, K, V>,
@@ -456,7 +370,6 @@ pub struct ValuesMut<'a, K: 'a, V: 'a> {
 ///
 /// [`range`]: struct.BTreeMap.html#method.range
 /// [`BTreeMap`]: struct.BTreeMap.html
-
 pub struct Range<'a, K: 'a, V: 'a> {
     front: Handle<NodeRef<marker::Immut<'a>, K, V, marker::Leaf>, marker::Edge>,
     back: Handle<NodeRef<marker::Immut<'a>, K, V, marker::Leaf>, marker::Edge>,
@@ -475,7 +388,6 @@ impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for Range<'_, K, V> {
 ///
 /// [`range_mut`]: struct.BTreeMap.html#method.range_mut
 /// [`BTreeMap`]: struct.BTreeMap.html
-
 pub struct RangeMut<'a, K: 'a, V: 'a> {
     front: Handle<NodeRef<marker::Mut<'a>, K, V, marker::Leaf>, marker::Edge>,
     back: Handle<NodeRef<marker::Mut<'a>, K, V, marker::Leaf>, marker::Edge>,
@@ -500,7 +412,6 @@ impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for RangeMut<'_, K, V> {
 ///
 /// [`BTreeMap`]: struct.BTreeMap.html
 /// [`entry`]: struct.BTreeMap.html#method.entry
-
 pub enum Entry<'a, K: 'a, V: 'a> {
     /// A vacant entry.
     Vacant(VacantEntry<'a, K, V>),
@@ -522,7 +433,6 @@ impl<K: Debug + Ord, V: Debug> Debug for Entry<'_, K, V> {
 /// It is part of the [`Entry`] enum.
 ///
 /// [`Entry`]: enum.Entry.html
-
 pub struct VacantEntry<'a, K: 'a, V: 'a> {
     key: K,
     handle: Handle<NodeRef<marker::Mut<'a>, K, V, marker::Leaf>, marker::Edge>,
@@ -542,7 +452,6 @@ impl<K: Debug + Ord, V> Debug for VacantEntry<'_, K, V> {
 /// It is part of the [`Entry`] enum.
 ///
 /// [`Entry`]: enum.Entry.html
-
 pub struct OccupiedEntry<'a, K: 'a, V: 'a> {
     handle: Handle<NodeRef<marker::Mut<'a>, K, V, marker::LeafOrInternal>, marker::KV>,
 
@@ -582,7 +491,6 @@ impl<K: Ord, V> BTreeMap<K, V> {
     /// // entries can now be inserted into the empty map
     /// map.insert(1, "a");
     /// ```
-
     pub fn new() -> BTreeMap<K, V> {
         BTreeMap {
             root: node::Root::shared_empty_root(),
@@ -604,7 +512,6 @@ impl<K: Ord, V> BTreeMap<K, V> {
     /// a.clear();
     /// assert!(a.is_empty());
     /// ```
-
     pub fn clear(&mut self) {
         *self = BTreeMap::new();
     }
@@ -626,11 +533,10 @@ impl<K: Ord, V> BTreeMap<K, V> {
     /// assert_eq!(map.get(&1), Some(&"a"));
     /// assert_eq!(map.get(&2), None);
     /// ```
-
-    pub fn get<Q: ?Sized>(&self, key: &Q) -> Option<&V>
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
     where
         K: Borrow<Q>,
-        Q: Ord,
+        Q: Ord + ?Sized,
     {
         match search::search_tree(self.root.as_ref(), key) {
             Found(handle) => Some(handle.into_kv().1),
@@ -646,7 +552,6 @@ impl<K: Ord, V> BTreeMap<K, V> {
     /// # Examples
     ///
     /// ```
-    /// #![feature(map_get_key_value)]
     /// use std::collections::BTreeMap;
     ///
     /// let mut map = BTreeMap::new();
@@ -654,10 +559,10 @@ impl<K: Ord, V> BTreeMap<K, V> {
     /// assert_eq!(map.get_key_value(&1), Some((&1, &"a")));
     /// assert_eq!(map.get_key_value(&2), None);
     /// ```
-    pub fn get_key_value<Q: ?Sized>(&self, k: &Q) -> Option<(&K, &V)>
+    pub fn get_key_value<Q>(&self, k: &Q) -> Option<(&K, &V)>
     where
         K: Borrow<Q>,
-        Q: Ord,
+        Q: Ord + ?Sized,
     {
         match search::search_tree(self.root.as_ref(), k) {
             Found(handle) => Some(handle.into_kv()),
@@ -682,12 +587,11 @@ impl<K: Ord, V> BTreeMap<K, V> {
     /// assert_eq!(map.contains_key(&1), true);
     /// assert_eq!(map.contains_key(&2), false);
     /// ```
-
     #[inline]
-    pub fn contains_key<Q: ?Sized>(&self, key: &Q) -> bool
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
     where
         K: Borrow<Q>,
-        Q: Ord,
+        Q: Ord + ?Sized,
     {
         self.get(key).is_some()
     }
@@ -712,11 +616,10 @@ impl<K: Ord, V> BTreeMap<K, V> {
     /// assert_eq!(map[&1], "b");
     /// ```
     // See `get` for implementation notes, this is basically a copy-paste with mut's added
-
-    pub fn get_mut<Q: ?Sized>(&mut self, key: &Q) -> Option<&mut V>
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
     where
         K: Borrow<Q>,
-        Q: Ord,
+        Q: Ord + ?Sized,
     {
         match search::search_tree(self.root.as_mut(), key) {
             Found(handle) => Some(handle.into_kv_mut().1),
@@ -750,7 +653,6 @@ impl<K: Ord, V> BTreeMap<K, V> {
     /// assert_eq!(map.insert(37, "c"), Some("b"));
     /// assert_eq!(map[&37], "c");
     /// ```
-
     pub fn try_insert(&mut self, key: K, value: V) -> Result<Option<V>, TryReserveError> {
         match self.try_entry(key)? {
             Occupied(mut entry) => Ok(Some(entry.insert(value))),
@@ -779,11 +681,10 @@ impl<K: Ord, V> BTreeMap<K, V> {
     /// assert_eq!(map.remove(&1), Some("a"));
     /// assert_eq!(map.remove(&1), None);
     /// ```
-
-    pub fn remove<Q: ?Sized>(&mut self, key: &Q) -> Option<V>
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
     where
         K: Borrow<Q>,
-        Q: Ord,
+        Q: Ord + ?Sized,
     {
         match search::search_tree(self.root.as_mut(), key) {
             Found(handle) => Some(
@@ -800,22 +701,25 @@ impl<K: Ord, V> BTreeMap<K, V> {
 
     /// Moves all elements from `other` into `Self`, leaving `other` empty.
     ///
+    /// Every node of the merged tree is allocated before any element is moved, so
+    /// if the allocation fails, both maps are left unchanged.
+    ///
     /// # Examples
     ///
     /// ```
-    /// use std::collections::BTreeMap;
+    /// use fallible_collections::btree::BTreeMap;
     ///
     /// let mut a = BTreeMap::new();
-    /// a.insert(1, "a");
-    /// a.insert(2, "b");
-    /// a.insert(3, "c");
+    /// a.try_insert(1, "a").unwrap();
+    /// a.try_insert(2, "b").unwrap();
+    /// a.try_insert(3, "c").unwrap();
     ///
     /// let mut b = BTreeMap::new();
-    /// b.insert(3, "d");
-    /// b.insert(4, "e");
-    /// b.insert(5, "f");
+    /// b.try_insert(3, "d").unwrap();
+    /// b.try_insert(4, "e").unwrap();
+    /// b.try_insert(5, "f").unwrap();
     ///
-    /// a.append(&mut b);
+    /// a.try_append(&mut b).unwrap();
     ///
     /// assert_eq!(a.len(), 5);
     /// assert_eq!(b.len(), 0);
@@ -826,29 +730,46 @@ impl<K: Ord, V> BTreeMap<K, V> {
     /// assert_eq!(a[&4], "e");
     /// assert_eq!(a[&5], "f");
     /// ```
-
-    pub fn append(&mut self, other: &mut Self) {
+    pub fn try_append(&mut self, other: &mut Self) -> Result<(), TryReserveError> {
         // Do we have to append anything at all?
-        if other.len() == 0 {
-            return;
+        if other.is_empty() {
+            return Ok(());
         }
 
         // We can just swap `self` and `other` if `self` is empty.
-        if self.len() == 0 {
+        if self.is_empty() {
+            mem::swap(self, other);
+            return Ok(());
+        }
+
+        // Allocate the nodes of the merged tree up front, there may be fewer elements
+        // than that if some keys are in both maps.
+        let mut pool = sorted_iter_node_pool(self.len() + other.len())?;
+        self.append_in(other, &mut pool);
+        Ok(())
+    }
+
+    /// Same as `try_append`, but builds the merged tree out of `pool`, which must hold the
+    /// nodes given by `sorted_iter_node_pool` for the length of the merged map.
+    pub(crate) fn append_in(&mut self, other: &mut Self, pool: &mut NodePool<K, V>) {
+        if other.is_empty() {
+            return;
+        }
+        if self.is_empty() {
             mem::swap(self, other);
             return;
         }
 
         // First, we merge `self` and `other` into a sorted sequence in linear time.
-        let self_iter = mem::replace(self, BTreeMap::new()).into_iter();
-        let other_iter = mem::replace(other, BTreeMap::new()).into_iter();
+        let self_iter = core::mem::take(self).into_iter();
+        let other_iter = core::mem::take(other).into_iter();
         let iter = MergeIter {
             left: self_iter.peekable(),
             right: other_iter.peekable(),
         };
 
         // Second, we build a tree from the sorted sequence in linear time.
-        self.from_sorted_iter(iter);
+        self.from_sorted_iter(iter, pool);
         self.fix_right_edge();
     }
 
@@ -881,10 +802,9 @@ impl<K: Ord, V> BTreeMap<K, V> {
     /// }
     /// assert_eq!(Some((&5, &"b")), map.range(4..).next());
     /// ```
-
-    pub fn range<T: ?Sized, R>(&self, range: R) -> Range<'_, K, V>
+    pub fn range<T, R>(&self, range: R) -> Range<'_, K, V>
     where
-        T: Ord,
+        T: Ord + ?Sized,
         K: Borrow<T>,
         R: RangeBounds<T>,
     {
@@ -925,10 +845,9 @@ impl<K: Ord, V> BTreeMap<K, V> {
     ///     println!("{} => {}", name, balance);
     /// }
     /// ```
-
-    pub fn range_mut<T: ?Sized, R>(&mut self, range: R) -> RangeMut<'_, K, V>
+    pub fn range_mut<T, R>(&mut self, range: R) -> RangeMut<'_, K, V>
     where
-        T: Ord,
+        T: Ord + ?Sized,
         K: Borrow<T>,
         R: RangeBounds<T>,
     {
@@ -961,7 +880,6 @@ impl<K: Ord, V> BTreeMap<K, V> {
     ///
     /// assert_eq!(count["a"], 3);
     /// ```
-
     pub fn try_entry(&mut self, key: K) -> Result<Entry<'_, K, V>, TryReserveError> {
         // FIXME(@porglezomp) Avoid allocating if we don't insert
         self.ensure_root_is_owned()?;
@@ -980,8 +898,43 @@ impl<K: Ord, V> BTreeMap<K, V> {
         })
     }
 
-    fn from_sorted_iter<I: Iterator<Item = (K, V)>>(&mut self, iter: I) {
-        self.ensure_root_is_owned().expect("Out Of Mem");
+    /// Retains only the elements for which `f` returns true, visiting them
+    /// in ascending order. Removing never allocates, so this can't fail.
+    pub(crate) fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&K, &mut V) -> bool,
+    {
+        let mut edge = first_leaf_edge(self.root.as_mut());
+        while let Some(mut kv) = next_kv(edge) {
+            let keep = {
+                let (key, value) = kv.kv_mut();
+                f(key, value)
+            };
+            if keep {
+                edge = next_leaf_edge(kv);
+                continue;
+            }
+            let (key, _) = OccupiedEntry {
+                handle: kv,
+                length: &mut self.length,
+                _marker: PhantomData,
+            }
+            .remove_kv();
+            // the removal may have merged nodes, so find where the removed
+            // key was to carry on from there
+            edge = match search::search_tree(self.root.as_mut(), &key) {
+                GoDown(edge) => edge,
+                Found(_) => unreachable!("the key was just removed"),
+            };
+        }
+    }
+
+    /// `pool` must hold the nodes given by `sorted_iter_node_pool` for the length of `iter`.
+    #[allow(clippy::wrong_self_convention)]
+    fn from_sorted_iter<I: Iterator<Item = (K, V)>>(&mut self, iter: I, pool: &mut NodePool<K, V>) {
+        if self.root.is_shared_root() {
+            self.root = node::Root::new_leaf_in(pool);
+        }
         let mut cur_node = last_leaf_edge(self.root.as_mut()).into_node();
         // Iterate through all key-value pairs, pushing them into nodes at the right level.
         for (key, value) in iter {
@@ -1007,7 +960,7 @@ impl<K: Ord, V> BTreeMap<K, V> {
                         }
                         Err(node) => {
                             // We are at the top, create a new root node and push there.
-                            open_node = node.into_root_mut().push_level().expect("Out of Mem");
+                            open_node = node.into_root_mut().push_level_in(pool);
                             break;
                         }
                     }
@@ -1015,9 +968,9 @@ impl<K: Ord, V> BTreeMap<K, V> {
 
                 // Push key-value pair and new right subtree.
                 let tree_height = open_node.height() - 1;
-                let mut right_tree = node::Root::new_leaf().expect("Out of Mem");
+                let mut right_tree = node::Root::new_leaf_in(pool);
                 for _ in 0..tree_height {
-                    right_tree.push_level().expect("Out of Mem");
+                    right_tree.push_level_in(pool);
                 }
                 open_node.push(key, value, right_tree);
 
@@ -1080,7 +1033,6 @@ impl<K: Ord, V> BTreeMap<K, V> {
     /// assert_eq!(b[&17], "d");
     /// assert_eq!(b[&41], "e");
     /// ```
-
     pub fn split_off<Q: ?Sized + Ord>(&mut self, key: &Q) -> Result<Self, TryReserveError>
     where
         K: Borrow<Q>,
@@ -1782,28 +1734,77 @@ impl<'a, K, V> RangeMut<'a, K, V> {
     }
 }
 
-impl<K: Ord, V> FromIterator<(K, V)> for BTreeMap<K, V> {
-    #[inline]
-    fn from_iter<T: IntoIterator<Item = (K, V)>>(iter: T) -> BTreeMap<K, V> {
+impl<K: Ord, V> BTreeMap<K, V> {
+    /// Creates a map from the key-value pairs of an iterator, returning an
+    /// error if the allocation of a node fails.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fallible_collections::btree::BTreeMap;
+    ///
+    /// let map = BTreeMap::try_from_iter(vec![(2, "b"), (1, "a")]).unwrap();
+    /// assert_eq!(map.len(), 2);
+    /// assert_eq!(map[&1], "a");
+    /// ```
+    pub fn try_from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Result<Self, TryReserveError> {
         let mut map = BTreeMap::new();
-        map.extend(iter);
-        map
+        map.try_extend(iter)?;
+        Ok(map)
+    }
+
+    /// Inserts the key-value pairs of an iterator into the map. On
+    /// allocation failure, the pairs inserted so far are kept and the rest
+    /// of the iterator is dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fallible_collections::btree::BTreeMap;
+    ///
+    /// let mut map = BTreeMap::new();
+    /// map.try_insert(1, "a").unwrap();
+    /// map.try_extend(vec![(1, "b"), (2, "c")]).unwrap();
+    /// assert_eq!(map[&1], "b");
+    /// assert_eq!(map[&2], "c");
+    /// ```
+    pub fn try_extend<I: IntoIterator<Item = (K, V)>>(
+        &mut self,
+        iter: I,
+    ) -> Result<(), TryReserveError> {
+        for (k, v) in iter {
+            self.try_insert(k, v)?;
+        }
+        Ok(())
     }
 }
 
-impl<K: Ord, V> Extend<(K, V)> for BTreeMap<K, V> {
+impl<K: Ord, V> TryExtend<(K, V)> for BTreeMap<K, V> {
+    #[inline(always)]
+    fn try_extend<T: IntoIterator<Item = (K, V)>>(
+        &mut self,
+        iter: T,
+    ) -> Result<(), TryReserveError> {
+        BTreeMap::try_extend(self, iter)
+    }
+}
+
+impl<'a, K: Ord + Copy, V: Copy> TryExtend<(&'a K, &'a V)> for BTreeMap<K, V> {
     #[inline]
-    fn extend<T: IntoIterator<Item = (K, V)>>(&mut self, iter: T) {
-        iter.into_iter().for_each(move |(k, v)| {
-            self.try_insert(k, v).expect("Out of Mem");
-        });
+    fn try_extend<I>(&mut self, iter: I) -> Result<(), TryReserveError>
+    where
+        I: IntoIterator<Item = (&'a K, &'a V)>,
+    {
+        BTreeMap::try_extend(self, iter.into_iter().map(|(&key, &value)| (key, value)))
     }
 }
 
-impl<'a, K: Ord + Copy, V: Copy> Extend<(&'a K, &'a V)> for BTreeMap<K, V> {
+impl<K: Ord, V> TryFromIterator<(K, V)> for BTreeMap<K, V> {
     #[inline]
-    fn extend<I: IntoIterator<Item = (&'a K, &'a V)>>(&mut self, iter: I) {
-        self.extend(iter.into_iter().map(|(&key, &value)| (key, value)));
+    fn try_from_iterator<T: IntoIterator<Item = (K, V)>>(
+        iterator: T,
+    ) -> Result<Self, TryReserveError> {
+        BTreeMap::try_from_iter(iterator)
     }
 }
 
@@ -1882,6 +1883,33 @@ fn first_leaf_edge<BorrowType, K, V>(
     }
 }
 
+/// Finds the first key/value pair right of a leaf edge, going up the tree
+/// while the edge is the last one of its node.
+fn next_kv<BorrowType, K, V>(
+    edge: Handle<NodeRef<BorrowType, K, V, marker::Leaf>, marker::Edge>,
+) -> Option<Handle<NodeRef<BorrowType, K, V, marker::LeafOrInternal>, marker::KV>> {
+    let mut edge = edge.forget_node_type();
+    loop {
+        edge = match edge.right_kv() {
+            Ok(kv) => return Some(kv),
+            Err(last_edge) => match last_edge.into_node().ascend() {
+                Ok(parent_edge) => parent_edge.forget_node_type(),
+                Err(_) => return None,
+            },
+        }
+    }
+}
+
+/// Finds the leaf edge right after a key/value pair.
+fn next_leaf_edge<BorrowType, K, V>(
+    kv: Handle<NodeRef<BorrowType, K, V, marker::LeafOrInternal>, marker::KV>,
+) -> Handle<NodeRef<BorrowType, K, V, marker::Leaf>, marker::Edge> {
+    match kv.force() {
+        Leaf(leaf_kv) => leaf_kv.right_edge(),
+        Internal(internal_kv) => first_leaf_edge(internal_kv.right_edge().descend()),
+    }
+}
+
 fn last_leaf_edge<BorrowType, K, V>(
     mut node: NodeRef<BorrowType, K, V, marker::LeafOrInternal>,
 ) -> Handle<NodeRef<BorrowType, K, V, marker::Leaf>, marker::Edge> {
@@ -1895,7 +1923,7 @@ fn last_leaf_edge<BorrowType, K, V>(
     }
 }
 
-fn range_search<BorrowType, K, V, Q: ?Sized, R: RangeBounds<Q>>(
+fn range_search<BorrowType, K, V, Q, R: RangeBounds<Q>>(
     root1: NodeRef<BorrowType, K, V, marker::LeafOrInternal>,
     root2: NodeRef<BorrowType, K, V, marker::LeafOrInternal>,
     range: R,
@@ -1904,7 +1932,7 @@ fn range_search<BorrowType, K, V, Q: ?Sized, R: RangeBounds<Q>>(
     Handle<NodeRef<BorrowType, K, V, marker::Leaf>, marker::Edge>,
 )
 where
-    Q: Ord,
+    Q: Ord + ?Sized,
     K: Borrow<Q>,
 {
     match (range.start_bound(), range.end_bound()) {
@@ -1999,7 +2027,7 @@ unsafe fn unwrap_unchecked<T>(val: Option<T>) -> T {
         if cfg!(debug_assertions) {
             panic!("'unchecked' unwrap on None in BTreeMap");
         } else {
-            intrinsics::unreachable();
+            hint::unreachable_unchecked();
         }
     })
 }
@@ -2026,7 +2054,6 @@ impl<K, V> BTreeMap<K, V> {
     /// let (first_key, first_value) = map.iter().next().unwrap();
     /// assert_eq!((*first_key, *first_value), (1, "a"));
     /// ```
-
     pub fn iter(&self) -> Iter<'_, K, V> {
         Iter {
             range: Range {
@@ -2058,7 +2085,6 @@ impl<K, V> BTreeMap<K, V> {
     ///     }
     /// }
     /// ```
-
     pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
         let root1 = self.root.as_mut();
         let root2 = unsafe { ptr::read(&root1) };
@@ -2088,7 +2114,6 @@ impl<K, V> BTreeMap<K, V> {
     /// let keys: Vec<_> = a.keys().cloned().collect();
     /// assert_eq!(keys, [1, 2]);
     /// ```
-
     #[inline(always)]
     pub fn keys<'a>(&'a self) -> Keys<'a, K, V> {
         Keys { inner: self.iter() }
@@ -2110,7 +2135,6 @@ impl<K, V> BTreeMap<K, V> {
     /// let values: Vec<&str> = a.values().cloned().collect();
     /// assert_eq!(values, ["hello", "goodbye"]);
     /// ```
-
     #[inline(always)]
     pub fn values<'a>(&'a self) -> Values<'a, K, V> {
         Values { inner: self.iter() }
@@ -2137,7 +2161,6 @@ impl<K, V> BTreeMap<K, V> {
     /// assert_eq!(values, [String::from("hello!"),
     ///                     String::from("goodbye!")]);
     /// ```
-
     #[inline(always)]
     pub fn values_mut(&mut self) -> ValuesMut<'_, K, V> {
         ValuesMut {
@@ -2159,7 +2182,6 @@ impl<K, V> BTreeMap<K, V> {
     /// a.insert(1, "a");
     /// assert_eq!(a.len(), 1);
     /// ```
-
     #[inline(always)]
     pub fn len(&self) -> usize {
         self.length
@@ -2179,7 +2201,6 @@ impl<K, V> BTreeMap<K, V> {
     /// a.insert(1, "a");
     /// assert!(!a.is_empty());
     /// ```
-
     #[inline(always)]
     pub fn is_empty(&self) -> bool {
         self.len() == 0
@@ -2200,7 +2221,6 @@ impl<'a, K: Ord, V> Entry<'a, K, V> {
     ///
     /// assert_eq!(map["poneyland"], 12);
     /// ```
-
     pub fn or_try_insert(self, default: V) -> Result<&'a mut V, TryReserveError> {
         match self {
             Occupied(entry) => Ok(entry.into_mut()),
@@ -2223,7 +2243,6 @@ impl<'a, K: Ord, V> Entry<'a, K, V> {
     ///
     /// assert_eq!(map["poneyland"], "hoho".to_string());
     /// ```
-
     pub fn or_try_insert_with<F: FnOnce() -> V>(
         self,
         default: F,
@@ -2244,7 +2263,6 @@ impl<'a, K: Ord, V> Entry<'a, K, V> {
     /// let mut map: BTreeMap<&str, usize> = BTreeMap::new();
     /// assert_eq!(map.entry("poneyland").key(), &"poneyland");
     /// ```
-
     #[inline]
     pub fn key(&self) -> &K {
         match *self {
@@ -2273,7 +2291,6 @@ impl<'a, K: Ord, V> Entry<'a, K, V> {
     ///    .or_insert(42);
     /// assert_eq!(map["poneyland"], 43);
     /// ```
-
     pub fn and_modify<F>(self, f: F) -> Self
     where
         F: FnOnce(&mut V),
@@ -2324,7 +2341,6 @@ impl<'a, K: Ord, V> VacantEntry<'a, K, V> {
     /// let mut map: BTreeMap<&str, usize> = BTreeMap::new();
     /// assert_eq!(map.entry("poneyland").key(), &"poneyland");
     /// ```
-
     #[inline(always)]
     pub fn key(&self) -> &K {
         &self.key
@@ -2366,9 +2382,10 @@ impl<'a, K: Ord, V> VacantEntry<'a, K, V> {
     ///
     /// assert_eq!(count["a"], 3);
     /// ```
-
     pub fn try_insert(self, value: V) -> Result<&'a mut V, TryReserveError> {
-        *self.length += 1;
+        // every node the insertion may split is allocated up front, so that
+        // the tree is only touched once nothing can fail anymore
+        let mut pool = self.split_pool()?;
 
         let out_ptr;
 
@@ -2376,8 +2393,11 @@ impl<'a, K: Ord, V> VacantEntry<'a, K, V> {
         let mut ins_v;
         let mut ins_edge;
 
-        let mut cur_parent = match self.handle.insert(self.key, value)? {
-            (Fit(handle), _) => return Ok(handle.into_kv_mut().1),
+        let mut cur_parent = match self.handle.insert(self.key, value, &mut pool) {
+            (Fit(handle), _) => {
+                *self.length += 1;
+                return Ok(handle.into_kv_mut().1);
+            }
             (Split(left, k, v, right), ptr) => {
                 ins_k = k;
                 ins_v = v;
@@ -2389,8 +2409,8 @@ impl<'a, K: Ord, V> VacantEntry<'a, K, V> {
 
         loop {
             match cur_parent {
-                Ok(parent) => match parent.insert(ins_k, ins_v, ins_edge)? {
-                    Fit(_) => return Ok(unsafe { &mut *out_ptr }),
+                Ok(parent) => match parent.insert(ins_k, ins_v, ins_edge, &mut pool) {
+                    Fit(_) => break,
                     Split(left, k, v, right) => {
                         ins_k = k;
                         ins_v = v;
@@ -2399,11 +2419,46 @@ impl<'a, K: Ord, V> VacantEntry<'a, K, V> {
                     }
                 },
                 Err(root) => {
-                    root.push_level()?.push(ins_k, ins_v, ins_edge);
-                    return Ok(unsafe { &mut *out_ptr });
+                    root.push_level_in(&mut pool).push(ins_k, ins_v, ins_edge);
+                    break;
                 }
             }
         }
+        *self.length += 1;
+        Ok(unsafe { &mut *out_ptr })
+    }
+
+    /// Allocates the nodes inserting into this entry needs: a full leaf splits,
+    /// and the split climbs through every full ancestor, adding a new root if
+    /// it reaches the top.
+    fn split_pool(&self) -> Result<NodePool<K, V>, TryReserveError> {
+        let mut pool = NodePool::new();
+        let leaf = self.handle.reborrow().into_node();
+        if leaf.len() < node::CAPACITY {
+            return Ok(pool);
+        }
+        let mut internals = 0;
+        let mut cur = leaf.forget_type();
+        loop {
+            match cur.ascend() {
+                Ok(parent) => {
+                    let parent = parent.into_node();
+                    // the split stops in a parent with room left
+                    if parent.len() < node::CAPACITY {
+                        break;
+                    }
+                    internals += 1;
+                    cur = parent.forget_type();
+                }
+                // the root splits, pushing a new level
+                Err(_) => {
+                    internals += 1;
+                    break;
+                }
+            }
+        }
+        pool.try_reserve(1, internals)?;
+        Ok(pool)
     }
 }
 
@@ -2419,7 +2474,6 @@ impl<'a, K: Ord, V> OccupiedEntry<'a, K, V> {
     /// map.entry("poneyland").or_insert(12);
     /// assert_eq!(map.entry("poneyland").key(), &"poneyland");
     /// ```
-
     #[inline]
     pub fn key(&self) -> &K {
         self.handle.reborrow().into_kv().0
@@ -2444,7 +2498,6 @@ impl<'a, K: Ord, V> OccupiedEntry<'a, K, V> {
     /// // If now try to get the value, it will panic:
     /// // println!("{}", map["poneyland"]);
     /// ```
-
     #[inline]
     pub fn remove_entry(self) -> (K, V) {
         self.remove_kv()
@@ -2465,7 +2518,6 @@ impl<'a, K: Ord, V> OccupiedEntry<'a, K, V> {
     ///     assert_eq!(o.get(), &12);
     /// }
     /// ```
-
     #[inline]
     pub fn get(&self) -> &V {
         self.handle.reborrow().into_kv().1
@@ -2603,7 +2655,7 @@ impl<'a, K: Ord, V> OccupiedEntry<'a, K, V> {
         while cur_node.len() < node::CAPACITY / 2 {
             match handle_underfull_node(cur_node) {
                 AtRoot => break,
-                EmptyParent(_) => unreachable!(),
+                EmptyParent => unreachable!(),
                 Merged(parent) => {
                     if parent.len() == 0 {
                         // We must be at the root
@@ -2613,7 +2665,7 @@ impl<'a, K: Ord, V> OccupiedEntry<'a, K, V> {
                         cur_node = parent.forget_type();
                     }
                 }
-                Stole(_) => break,
+                Stole => break,
             }
         }
 
@@ -2623,9 +2675,9 @@ impl<'a, K: Ord, V> OccupiedEntry<'a, K, V> {
 
 enum UnderflowResult<'a, K, V> {
     AtRoot,
-    EmptyParent(NodeRef<marker::Mut<'a>, K, V, marker::Internal>),
+    EmptyParent,
     Merged(NodeRef<marker::Mut<'a>, K, V, marker::Internal>),
-    Stole(NodeRef<marker::Mut<'a>, K, V, marker::Internal>),
+    Stole,
 }
 
 fn handle_underfull_node<'a, K, V>(
@@ -2641,8 +2693,8 @@ fn handle_underfull_node<'a, K, V>(
         Ok(left) => (true, left),
         Err(parent) => match parent.right_kv() {
             Ok(right) => (false, right),
-            Err(parent) => {
-                return EmptyParent(parent.into_node());
+            Err(_) => {
+                return EmptyParent;
             }
         },
     };
@@ -2655,16 +2707,33 @@ fn handle_underfull_node<'a, K, V>(
         } else {
             handle.steal_right();
         }
-        Stole(handle.into_node())
+        Stole
     }
 }
 
+/// Allocates every node `from_sorted_iter` needs to build a tree of up to `len` elements.
+pub(crate) fn sorted_iter_node_pool<K, V>(len: usize) -> Result<NodePool<K, V>, TryReserveError> {
+    // Each node is filled up to `CAPACITY` before the next element is pushed one level up,
+    // along with a new right subtree holding one node for each level below.
+    let mut pushed_up = len / (node::CAPACITY + 1);
+    let leaves = 1 + pushed_up;
+    let mut internals = 0;
+    while pushed_up > 0 {
+        pushed_up /= node::CAPACITY + 1;
+        internals += 1 + pushed_up;
+    }
+
+    let mut pool = NodePool::new();
+    pool.try_reserve(leaves, internals)?;
+    Ok(pool)
+}
+
 impl<K: Ord, V, I: Iterator<Item = (K, V)>> Iterator for MergeIter<K, V, I> {
     type Item = (K, V);
 
     fn next(&mut self) -> Option<(K, V)> {
         let res = match (self.left.peek(), self.right.peek()) {
-            (Some(&(ref left_key, _)), Some(&(ref right_key, _))) => left_key.cmp(right_key),
+            (Some((left_key, _)), Some((right_key, _))) => left_key.cmp(right_key),
             (Some(_), None) => Ordering::Less,
             (None, Some(_)) => Ordering::Greater,
             (None, None) => return None,
@@ -2682,3 +2751,169 @@ impl<K: Ord, V, I: Iterator<Item = (K, V)>> Iterator for MergeIter<K, V, I> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn try_insert_and_remove() {
+        let mut map = BTreeMap::new();
+        for i in 0..1000 {
+            assert_eq!(map.try_insert(i, i * 2).unwrap(), None);
+        }
+        assert_eq!(map.len(), 1000);
+        assert_eq!(map.try_insert(7, 0).unwrap(), Some(14));
+        assert_eq!(map.get(&500), Some(&1000));
+        for i in (0..1000).step_by(2) {
+            assert!(map.remove(&i).is_some());
+        }
+        assert_eq!(map.len(), 500);
+        assert!(map.keys().copied().eq((1..1000).step_by(2)));
+    }
+
+    #[test]
+    fn try_insert_oom_keeps_map_intact() {
+        use crate::test_alloc::with_alloc_budget;
+
+        // a full leaf root has to split
+        let mut map = BTreeMap::try_from_iter((0..node::CAPACITY).map(|i| (i, i))).unwrap();
+        assert!(with_alloc_budget(0, || map.try_insert(node::CAPACITY, 0)).is_err());
+        assert_eq!(map.len(), node::CAPACITY);
+        assert!(map.keys().copied().eq(0..node::CAPACITY));
+
+        // every kind of split on the way: leaves, internal nodes and new roots
+        let mut map = BTreeMap::new();
+        for i in 0..2000 {
+            for budget in 0..2 {
+                if with_alloc_budget(budget, || map.try_insert(i, i)).is_ok() {
+                    break;
+                }
+                assert_eq!(map.len(), i);
+                assert!(map.keys().copied().eq(0..i));
+                assert!(map.iter().rev().map(|(&k, _)| k).eq((0..i).rev()));
+            }
+            if map.len() == i {
+                map.try_insert(i, i).unwrap();
+            }
+        }
+        assert!(map
+            .iter()
+            .map(|(&k, &v)| (k, v))
+            .eq((0..2000).map(|i| (i, i))));
+    }
+
+    #[test]
+    fn retain() {
+        // deep enough for removals to merge and steal across levels
+        let mut map = BTreeMap::try_from_iter((0..1000).map(|i| (i, i * 2))).unwrap();
+        map.retain(|&k, v| {
+            *v += 1;
+            k % 3 != 0 && !(300..700).contains(&k)
+        });
+        let expected = (0..1000).filter(|k| k % 3 != 0 && !(300..700).contains(k));
+        assert!(map
+            .iter()
+            .map(|(&k, &v)| (k, v))
+            .eq(expected.clone().map(|k| (k, k * 2 + 1))));
+        assert_eq!(map.len(), expected.clone().count());
+        assert!(map.iter().rev().map(|(&k, _)| k).eq(expected.rev()));
+
+        map.retain(|_, _| false);
+        assert!(map.is_empty());
+        assert_eq!(map.iter().count(), 0);
+        map.try_insert(1, 1).unwrap();
+        assert_eq!(map.get(&1), Some(&1));
+
+        let mut empty = BTreeMap::<u32, u32>::new();
+        empty.retain(|_, _| false);
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn try_entry() {
+        let mut map = BTreeMap::new();
+        *map.try_entry("a").unwrap().or_try_insert(1).unwrap() += 10;
+        *map.try_entry("a").unwrap().or_try_insert(1).unwrap() += 10;
+        *map.try_entry("b").unwrap().or_default().unwrap() += 1;
+        assert_eq!(map.get("a"), Some(&21));
+        assert_eq!(map.get("b"), Some(&1));
+    }
+
+    #[test]
+    fn split_off() {
+        let mut map = BTreeMap::new();
+        for i in 0..100 {
+            map.try_insert(i, ()).unwrap();
+        }
+        let right = map.split_off(&60).unwrap();
+        assert!(map.keys().copied().eq(0..60));
+        assert!(right.keys().copied().eq(60..100));
+    }
+
+    #[test]
+    fn try_append() {
+        for &(left, right) in &[
+            (0, 5),
+            (5, 0),
+            (1, 1),
+            (11, 1),
+            (100, 100),
+            (1000, 3000),
+            (20000, 800),
+        ] {
+            let mut a = BTreeMap::new();
+            let mut b = BTreeMap::new();
+            for i in 0..left {
+                a.try_insert(i * 2, 0).unwrap();
+            }
+            for i in 0..right {
+                b.try_insert(i * 3, 1).unwrap();
+            }
+            a.try_append(&mut b).unwrap();
+            assert!(b.is_empty());
+            let mut expected = std::collections::BTreeMap::new();
+            expected.extend((0..left).map(|i| (i * 2, 0)));
+            expected.extend((0..right).map(|i| (i * 3, 1)));
+            assert_eq!(a.len(), expected.len());
+            assert!(a.iter().eq(expected.iter()));
+        }
+    }
+
+    #[test]
+    fn try_append_pool_is_exact() {
+        let boundaries = [1727, 1728, 1729, 1740, 20735, 20736, 20737, 20748];
+        for len in (0..300).chain(boundaries.iter().copied()) {
+            let mut pool = sorted_iter_node_pool::<u32, ()>(len).unwrap();
+            let mut map = BTreeMap::new();
+            map.from_sorted_iter((0..len as u32).map(|i| (i, ())), &mut pool);
+            assert!(pool.is_empty());
+        }
+    }
+
+    #[test]
+    fn try_clone() {
+        let mut map = BTreeMap::new();
+        for i in 0..100 {
+            map.try_insert(i, i).unwrap();
+        }
+        assert_eq!(map.try_clone().unwrap(), map);
+    }
+
+    #[test]
+    fn try_from_iter_and_extend() {
+        let mut map = BTreeMap::try_from_iter((0..100).map(|i| (i, i))).unwrap();
+        map.try_extend((50..150).map(|i| (i, i * 2))).unwrap();
+        assert_eq!(map.len(), 150);
+        assert_eq!(map[&10], 10);
+        assert_eq!(map[&60], 120);
+    }
+
+    #[test]
+    fn try_collect() {
+        use crate::TryCollect;
+
+        let map: BTreeMap<_, _> = (0..100).rev().map(|i| (i, i)).try_collect().unwrap();
+        assert!(map.keys().copied().eq(0..100));
+    }
+}
@@ -33,12 +33,12 @@
 
 use core::marker::PhantomData;
 use core::mem::{self, MaybeUninit};
-use core::ptr::{self, NonNull, Unique};
+use core::ptr::{self, NonNull};
 use core::slice;
 
 use crate::boxed::FallibleBox;
 use crate::TryReserveError;
-use alloc::alloc::{Allocator, Global, Layout};
+use alloc::alloc::{dealloc, Layout};
 use alloc::boxed::Box;
 
 const B: usize = 6;
@@ -108,8 +108,8 @@ impl<K, V> LeafNode<K, V> {
         LeafNode {
             // As a general policy, we leave fields uninitialized if they can be, as this should
             // be both slightly faster and easier to track in Valgrind.
-            keys: MaybeUninit::uninit_array::<CAPACITY>(),
-            vals: MaybeUninit::uninit_array::<CAPACITY>(),
+            keys: unsafe { MaybeUninit::uninit().assume_init() },
+            vals: unsafe { MaybeUninit::uninit().assume_init() },
             parent: ptr::null(),
             parent_idx: MaybeUninit::uninit(),
             len: 0,
@@ -161,7 +161,7 @@ impl<K, V> InternalNode<K, V> {
     unsafe fn new() -> Self {
         InternalNode {
             data: LeafNode::new(),
-            edges: MaybeUninit::uninit_array::<{ 2 * B }>(),
+            edges: unsafe { MaybeUninit::uninit().assume_init() },
         }
     }
 }
@@ -171,31 +171,32 @@ impl<K, V> InternalNode<K, V> {
 /// of nodes is actually behind the box, and, partially due to this lack of information, has no
 /// destructor.
 struct BoxedNode<K, V> {
-    ptr: Unique<LeafNode<K, V>>,
+    ptr: NonNull<LeafNode<K, V>>,
 }
 
 impl<K, V> BoxedNode<K, V> {
     fn from_leaf(node: Box<LeafNode<K, V>>) -> Self {
-        let (ptr, _g) = Box::into_unique(node);
-        BoxedNode { ptr: ptr }
+        unsafe {
+            BoxedNode {
+                ptr: NonNull::new_unchecked(Box::into_raw(node)),
+            }
+        }
     }
 
     fn from_internal(node: Box<InternalNode<K, V>>) -> Self {
         unsafe {
             BoxedNode {
-                ptr: Unique::new_unchecked(Box::into_raw(node) as *mut LeafNode<K, V>),
+                ptr: NonNull::new_unchecked(Box::into_raw(node) as *mut LeafNode<K, V>),
             }
         }
     }
 
     unsafe fn from_ptr(ptr: NonNull<LeafNode<K, V>>) -> Self {
-        BoxedNode {
-            ptr: Unique::new_unchecked(ptr.as_ptr()),
-        }
+        BoxedNode { ptr }
     }
 
     fn as_ptr(&self) -> NonNull<LeafNode<K, V>> {
-        NonNull::from(self.ptr)
+        self.ptr
     }
 }
 
@@ -266,8 +267,31 @@ impl<K, V> Root<K, V> {
     pub fn push_level(
         &mut self,
     ) -> Result<NodeRef<marker::Mut<'_>, K, V, marker::Internal>, TryReserveError> {
+        let new_node = <Box<_> as FallibleBox<_>>::try_new(unsafe { InternalNode::new() })?;
+        Ok(self.push_level_with(new_node))
+    }
+
+    /// Creates a new leaf root out of a node of `pool`, this cannot fail.
+    pub fn new_leaf_in(pool: &mut NodePool<K, V>) -> Self {
+        Root {
+            node: BoxedNode::from_leaf(pool.take_leaf()),
+            height: 0,
+        }
+    }
+
+    /// Same as `push_level`, but takes the new root out of `pool` so it cannot fail.
+    pub fn push_level_in(
+        &mut self,
+        pool: &mut NodePool<K, V>,
+    ) -> NodeRef<marker::Mut<'_>, K, V, marker::Internal> {
+        self.push_level_with(pool.take_internal())
+    }
+
+    fn push_level_with(
+        &mut self,
+        mut new_node: Box<InternalNode<K, V>>,
+    ) -> NodeRef<marker::Mut<'_>, K, V, marker::Internal> {
         debug_assert!(!self.is_shared_root());
-        let mut new_node = <Box<_> as FallibleBox<_>>::try_new(unsafe { InternalNode::new() })?;
         new_node.edges[0].write(unsafe { BoxedNode::from_ptr(self.node.as_ptr()) });
 
         self.node = BoxedNode::from_internal(new_node);
@@ -284,7 +308,7 @@ impl<K, V> Root<K, V> {
             ret.reborrow_mut().first_edge().correct_parent_link();
         }
 
-        Ok(ret)
+        ret
     }
 
     /// Removes the root node, using its first child as the new root. This cannot be called when
@@ -311,10 +335,84 @@ impl<K, V> Root<K, V> {
         }
 
         unsafe {
-            Global.deallocate(
-                NonNull::from(top).cast(),
-                Layout::new::<InternalNode<K, V>>(),
-            );
+            dealloc(top.cast().as_ptr(), Layout::new::<InternalNode<K, V>>());
+        }
+    }
+}
+
+/// Nodes allocated ahead of time, so that a tree can later be grown out of them without any
+/// allocation failure. The free nodes are chained through their `parent` pointer.
+pub struct NodePool<K, V> {
+    leaves: *mut LeafNode<K, V>,
+    internals: *mut InternalNode<K, V>,
+}
+
+impl<K, V> NodePool<K, V> {
+    pub fn new() -> Self {
+        NodePool {
+            leaves: ptr::null_mut(),
+            internals: ptr::null_mut(),
+        }
+    }
+
+    /// Allocates `leaves` more leaf nodes and `internals` more internal nodes. On failure the
+    /// nodes allocated so far stay in the pool and are freed along with it.
+    pub fn try_reserve(&mut self, leaves: usize, internals: usize) -> Result<(), TryReserveError> {
+        for _ in 0..leaves {
+            let node = Box::into_raw(<Box<_> as FallibleBox<_>>::try_new(unsafe {
+                LeafNode::new()
+            })?);
+            unsafe {
+                (*node).parent = self.leaves as *const InternalNode<K, V>;
+            }
+            self.leaves = node;
+        }
+        for _ in 0..internals {
+            let node = Box::into_raw(<Box<_> as FallibleBox<_>>::try_new(unsafe {
+                InternalNode::new()
+            })?);
+            unsafe {
+                (*node).data.parent = self.internals;
+            }
+            self.internals = node;
+        }
+        Ok(())
+    }
+
+    #[cfg(test)]
+    pub fn is_empty(&self) -> bool {
+        self.leaves.is_null() && self.internals.is_null()
+    }
+
+    fn take_leaf(&mut self) -> Box<LeafNode<K, V>> {
+        assert!(!self.leaves.is_null(), "node pool exhausted");
+        unsafe {
+            let node = self.leaves;
+            self.leaves = (*node).parent as *mut LeafNode<K, V>;
+            (*node).parent = ptr::null();
+            Box::from_raw(node)
+        }
+    }
+
+    fn take_internal(&mut self) -> Box<InternalNode<K, V>> {
+        assert!(!self.internals.is_null(), "node pool exhausted");
+        unsafe {
+            let node = self.internals;
+            self.internals = (*node).data.parent as *mut InternalNode<K, V>;
+            (*node).data.parent = ptr::null();
+            Box::from_raw(node)
+        }
+    }
+}
+
+impl<K, V> Drop for NodePool<K, V> {
+    fn drop(&mut self) {
+        // The nodes are empty, dropping them only frees their memory.
+        while !self.leaves.is_null() {
+            drop(self.take_leaf());
+        }
+        while !self.internals.is_null() {
+            drop(self.take_internal());
         }
     }
 }
@@ -328,9 +426,9 @@ impl<K, V> Root<K, V> {
 ///
 /// This type has a number of parameters that controls how it acts:
 /// - `BorrowType`: This can be `Immut<'a>` or `Mut<'a>` for some `'a` or `Owned`.
-///    When this is `Immut<'a>`, the `NodeRef` acts roughly like `&'a Node`,
-///    when this is `Mut<'a>`, the `NodeRef` acts roughly like `&'a mut Node`,
-///    and when this is `Owned`, the `NodeRef` acts roughly like `Box<Node>`.
+///   When this is `Immut<'a>`, the `NodeRef` acts roughly like `&'a Node`,
+///   when this is `Mut<'a>`, the `NodeRef` acts roughly like `&'a mut Node`,
+///   and when this is `Owned`, the `NodeRef` acts roughly like `Box<Node>`.
 /// - `K` and `V`: These control what types of things are stored in the nodes.
 /// - `Type`: This can be `Leaf`, `Internal`, or `LeafOrInternal`. When this is
 ///   `Leaf`, the `NodeRef` points to a leaf node, when this is `Internal` the
@@ -489,7 +587,7 @@ impl<K, V> NodeRef<marker::Owned, K, V, marker::Leaf> {
         debug_assert!(!self.is_shared_root());
         let node = self.node;
         let ret = self.ascend().ok();
-        Global.deallocate(node.cast(), Layout::new::<LeafNode<K, V>>());
+        dealloc(node.cast().as_ptr(), Layout::new::<LeafNode<K, V>>());
         ret
     }
 }
@@ -503,7 +601,7 @@ impl<K, V> NodeRef<marker::Owned, K, V, marker::Internal> {
     ) -> Option<Handle<NodeRef<marker::Owned, K, V, marker::Internal>, marker::Edge>> {
         let node = self.node;
         let ret = self.ascend().ok();
-        Global.deallocate(node.cast(), Layout::new::<InternalNode<K, V>>());
+        dealloc(node.cast().as_ptr(), Layout::new::<InternalNode<K, V>>());
         ret
     }
 }
@@ -598,9 +696,7 @@ impl<'a, K: 'a, V: 'a, Type> NodeRef<marker::Immut<'a>, K, V, Type> {
     fn into_val_slice(self) -> &'a [V] {
         debug_assert!(!self.is_shared_root());
         // We cannot be the root, so `as_leaf` is okay
-        unsafe {
-            slice::from_raw_parts(MaybeUninit::slice_as_ptr(&self.as_leaf().vals), self.len())
-        }
+        unsafe { slice::from_raw_parts(self.as_leaf().vals.as_ptr() as *const V, self.len()) }
     }
 
     fn into_slices(self) -> (&'a [K], &'a [V]) {
@@ -624,7 +720,7 @@ impl<'a, K: 'a, V: 'a, Type> NodeRef<marker::Mut<'a>, K, V, Type> {
         } else {
             unsafe {
                 slice::from_raw_parts_mut(
-                    MaybeUninit::slice_as_mut_ptr(&mut (*self.as_leaf_mut()).keys),
+                    (*self.as_leaf_mut()).keys.as_mut_ptr() as *mut K,
                     self.len(),
                 )
             }
@@ -635,7 +731,7 @@ impl<'a, K: 'a, V: 'a, Type> NodeRef<marker::Mut<'a>, K, V, Type> {
         debug_assert!(!self.is_shared_root());
         unsafe {
             slice::from_raw_parts_mut(
-                MaybeUninit::slice_as_mut_ptr(&mut (*self.as_leaf_mut()).vals),
+                (*self.as_leaf_mut()).vals.as_mut_ptr() as *mut V,
                 self.len(),
             )
         }
@@ -651,10 +747,8 @@ impl<'a, K: 'a, V: 'a, Type> NodeRef<marker::Mut<'a>, K, V, Type> {
         unsafe {
             let len = self.len();
             let leaf = self.as_leaf_mut();
-            let keys =
-                slice::from_raw_parts_mut(MaybeUninit::slice_as_mut_ptr(&mut (*leaf).keys), len);
-            let vals =
-                slice::from_raw_parts_mut(MaybeUninit::slice_as_mut_ptr(&mut (*leaf).vals), len);
+            let keys = slice::from_raw_parts_mut((*leaf).keys.as_mut_ptr() as *mut K, len);
+            let vals = slice::from_raw_parts_mut((*leaf).vals.as_mut_ptr() as *mut V, len);
             (keys, vals)
         }
     }
@@ -670,8 +764,8 @@ impl<'a, K, V> NodeRef<marker::Mut<'a>, K, V, marker::Leaf> {
         let idx = self.len();
 
         unsafe {
-            ptr::write(self.keys_mut().get_unchecked_mut(idx), key);
-            ptr::write(self.vals_mut().get_unchecked_mut(idx), val);
+            ptr::write(self.keys_mut().as_mut_ptr().add(idx), key);
+            ptr::write(self.vals_mut().as_mut_ptr().add(idx), val);
 
             (*self.as_leaf_mut()).len += 1;
         }
@@ -703,8 +797,8 @@ impl<'a, K, V> NodeRef<marker::Mut<'a>, K, V, marker::Internal> {
         let idx = self.len();
 
         unsafe {
-            ptr::write(self.keys_mut().get_unchecked_mut(idx), key);
-            ptr::write(self.vals_mut().get_unchecked_mut(idx), val);
+            ptr::write(self.keys_mut().as_mut_ptr().add(idx), key);
+            ptr::write(self.vals_mut().as_mut_ptr().add(idx), val);
             self.as_internal_mut()
                 .edges
                 .get_unchecked_mut(idx + 1)
@@ -739,7 +833,7 @@ impl<'a, K, V> NodeRef<marker::Mut<'a>, K, V, marker::Internal> {
             slice_insert(self.vals_mut(), 0, val);
             slice_insert(
                 slice::from_raw_parts_mut(
-                    MaybeUninit::slice_as_mut_ptr(&mut self.as_internal_mut().edges),
+                    self.as_internal_mut().edges.as_mut_ptr() as *mut BoxedNode<K, V>,
                     self.len() + 1,
                 ),
                 0,
@@ -800,7 +894,7 @@ impl<'a, K, V> NodeRef<marker::Mut<'a>, K, V, marker::LeafOrInternal> {
                 ForceResult::Internal(mut internal) => {
                     let edge = slice_remove(
                         slice::from_raw_parts_mut(
-                            MaybeUninit::slice_as_mut_ptr(&mut internal.as_internal_mut().edges),
+                            internal.as_internal_mut().edges.as_mut_ptr() as *mut BoxedNode<K, V>,
                             old_len + 1,
                         ),
                         0,
@@ -887,6 +981,22 @@ impl<Node, Type> Handle<Node, Type> {
     }
 }
 
+impl<BorrowType, K, V, NodeType, HandleType>
+    Handle<NodeRef<BorrowType, K, V, NodeType>, HandleType>
+{
+    /// Removes any static information about whether the node this handle points into
+    /// is a `Leaf` or an `Internal` node.
+    pub fn forget_node_type(
+        self,
+    ) -> Handle<NodeRef<BorrowType, K, V, marker::LeafOrInternal>, HandleType> {
+        Handle {
+            node: self.node.forget_type(),
+            idx: self.idx,
+            _marker: PhantomData,
+        }
+    }
+}
+
 impl<BorrowType, K, V, NodeType> Handle<NodeRef<BorrowType, K, V, NodeType>, marker::KV> {
     /// Creates a new handle to a key/value pair in `node`. `idx` must be less than `node.len()`.
     pub fn new_kv(node: NodeRef<BorrowType, K, V, NodeType>, idx: usize) -> Self {
@@ -1007,20 +1117,22 @@ impl<'a, K, V> Handle<NodeRef<marker::Mut<'a>, K, V, marker::Leaf>, marker::Edge
     }
 
     /// Inserts a new key/value pair between the key/value pairs to the right and left of
-    /// this edge. This method splits the node if there isn't enough room.
+    /// this edge. This method splits the node if there isn't enough room, taking the new node
+    /// out of `pool`.
     ///
     /// The returned pointer points to the inserted value.
     pub fn insert(
         mut self,
         key: K,
         val: V,
-    ) -> Result<(InsertResult<'a, K, V, marker::Leaf>, *mut V), TryReserveError> {
+        pool: &mut NodePool<K, V>,
+    ) -> (InsertResult<'a, K, V, marker::Leaf>, *mut V) {
         if self.node.len() < CAPACITY {
             let ptr = self.insert_fit(key, val);
-            Ok((InsertResult::Fit(Handle::new_kv(self.node, self.idx)), ptr))
+            (InsertResult::Fit(Handle::new_kv(self.node, self.idx)), ptr)
         } else {
             let middle = Handle::new_kv(self.node, B);
-            let (mut left, k, v, mut right) = middle.split()?;
+            let (mut left, k, v, mut right) = middle.split(pool);
             let ptr = if self.idx <= B {
                 unsafe { Handle::new_edge(left.reborrow_mut(), self.idx).insert_fit(key, val) }
             } else {
@@ -1032,7 +1144,7 @@ impl<'a, K, V> Handle<NodeRef<marker::Mut<'a>, K, V, marker::Leaf>, marker::Edge
                     .insert_fit(key, val)
                 }
             };
-            Ok((InsertResult::Split(left, k, v, right), ptr))
+            (InsertResult::Split(left, k, v, right), ptr)
         }
     }
 }
@@ -1072,7 +1184,7 @@ impl<'a, K, V> Handle<NodeRef<marker::Mut<'a>, K, V, marker::Internal>, marker::
 
             slice_insert(
                 slice::from_raw_parts_mut(
-                    MaybeUninit::slice_as_mut_ptr(&mut self.node.as_internal_mut().edges),
+                    self.node.as_internal_mut().edges.as_mut_ptr() as *mut BoxedNode<K, V>,
                     self.node.len(),
                 ),
                 self.idx + 1,
@@ -1087,22 +1199,23 @@ impl<'a, K, V> Handle<NodeRef<marker::Mut<'a>, K, V, marker::Internal>, marker::
 
     /// Inserts a new key/value pair and an edge that will go to the right of that new pair
     /// between this edge and the key/value pair to the right of this edge. This method splits
-    /// the node if there isn't enough room.
+    /// the node if there isn't enough room, taking the new node out of `pool`.
     pub fn insert(
         mut self,
         key: K,
         val: V,
         edge: Root<K, V>,
-    ) -> Result<InsertResult<'a, K, V, marker::Internal>, TryReserveError> {
+        pool: &mut NodePool<K, V>,
+    ) -> InsertResult<'a, K, V, marker::Internal> {
         // Necessary for correctness, but this is an internal module
         debug_assert!(edge.height == self.node.height - 1);
 
         if self.node.len() < CAPACITY {
             self.insert_fit(key, val, edge);
-            Ok(InsertResult::Fit(Handle::new_kv(self.node, self.idx)))
+            InsertResult::Fit(Handle::new_kv(self.node, self.idx))
         } else {
             let middle = Handle::new_kv(self.node, B);
-            let (mut left, k, v, mut right) = middle.split()?;
+            let (mut left, k, v, mut right) = middle.split(pool);
             if self.idx <= B {
                 unsafe {
                     Handle::new_edge(left.reborrow_mut(), self.idx).insert_fit(key, val, edge);
@@ -1116,7 +1229,7 @@ impl<'a, K, V> Handle<NodeRef<marker::Mut<'a>, K, V, marker::Internal>, marker::
                     .insert_fit(key, val, edge);
                 }
             }
-            Ok(InsertResult::Split(left, k, v, right))
+            InsertResult::Split(left, k, v, right)
         }
     }
 }
@@ -1181,22 +1294,20 @@ impl<'a, K, V> Handle<NodeRef<marker::Mut<'a>, K, V, marker::Leaf>, marker::KV>
     /// - The node is truncated to only contain the key/value pairs to the right of
     ///   this handle.
     /// - The key and value pointed to by this handle and extracted.
-    /// - All the key/value pairs to the right of this handle are put into a newly
-    ///   allocated node.
+    /// - All the key/value pairs to the right of this handle are put into a new node
+    ///   taken out of `pool`.
     pub fn split(
         mut self,
-    ) -> Result<
-        (
-            NodeRef<marker::Mut<'a>, K, V, marker::Leaf>,
-            K,
-            V,
-            Root<K, V>,
-        ),
-        TryReserveError,
-    > {
+        pool: &mut NodePool<K, V>,
+    ) -> (
+        NodeRef<marker::Mut<'a>, K, V, marker::Leaf>,
+        K,
+        V,
+        Root<K, V>,
+    ) {
         debug_assert!(!self.node.is_shared_root());
         unsafe {
-            let mut new_node = <Box<_> as FallibleBox<_>>::try_new(LeafNode::new())?;
+            let mut new_node = pool.take_leaf();
 
             let k = ptr::read(self.node.keys().get_unchecked(self.idx));
             let v = ptr::read(self.node.vals().get_unchecked(self.idx));
@@ -1217,7 +1328,7 @@ impl<'a, K, V> Handle<NodeRef<marker::Mut<'a>, K, V, marker::Leaf>, marker::KV>
             (*self.node.as_leaf_mut()).len = self.idx as u16;
             new_node.len = new_len as u16;
 
-            Ok((
+            (
                 self.node,
                 k,
                 v,
@@ -1225,7 +1336,7 @@ impl<'a, K, V> Handle<NodeRef<marker::Mut<'a>, K, V, marker::Leaf>, marker::KV>
                     node: BoxedNode::from_leaf(new_node),
                     height: 0,
                 },
-            ))
+            )
         }
     }
 
@@ -1255,20 +1366,18 @@ impl<'a, K, V> Handle<NodeRef<marker::Mut<'a>, K, V, marker::Internal>, marker::
     ///   right of this handle.
     /// - The key and value pointed to by this handle and extracted.
     /// - All the edges and key/value pairs to the right of this handle are put into
-    ///   a newly allocated node.
+    ///   a new node taken out of `pool`.
     pub fn split(
         mut self,
-    ) -> Result<
-        (
-            NodeRef<marker::Mut<'a>, K, V, marker::Internal>,
-            K,
-            V,
-            Root<K, V>,
-        ),
-        TryReserveError,
-    > {
+        pool: &mut NodePool<K, V>,
+    ) -> (
+        NodeRef<marker::Mut<'a>, K, V, marker::Internal>,
+        K,
+        V,
+        Root<K, V>,
+    ) {
         unsafe {
-            let mut new_node = <Box<_> as FallibleBox<_>>::try_new(InternalNode::new())?;
+            let mut new_node = pool.take_internal();
 
             let k = ptr::read(self.node.keys().get_unchecked(self.idx));
             let v = ptr::read(self.node.vals().get_unchecked(self.idx));
@@ -1304,7 +1413,7 @@ impl<'a, K, V> Handle<NodeRef<marker::Mut<'a>, K, V, marker::Internal>, marker::
                 Handle::new_edge(new_root.as_mut().cast_unchecked(), i).correct_parent_link();
             }
 
-            Ok((self.node, k, v, new_root))
+            (self.node, k, v, new_root)
         }
     }
 
@@ -1334,11 +1443,11 @@ impl<'a, K, V> Handle<NodeRef<marker::Mut<'a>, K, V, marker::Internal>, marker::
         let right_len = right_node.len();
 
         // necessary for correctness, but in a private module
-        debug_assert!(left_len + right_len + 1 <= CAPACITY);
+        debug_assert!(left_len + right_len < CAPACITY);
 
         unsafe {
             ptr::write(
-                left_node.keys_mut().get_unchecked_mut(left_len),
+                left_node.keys_mut().as_mut_ptr().add(left_len),
                 slice_remove(self.node.keys_mut(), self.idx),
             );
             ptr::copy_nonoverlapping(
@@ -1347,7 +1456,7 @@ impl<'a, K, V> Handle<NodeRef<marker::Mut<'a>, K, V, marker::Internal>, marker::
                 right_len,
             );
             ptr::write(
-                left_node.vals_mut().get_unchecked_mut(left_len),
+                left_node.vals_mut().as_mut_ptr().add(left_len),
                 slice_remove(self.node.vals_mut(), self.idx),
             );
             ptr::copy_nonoverlapping(
@@ -1381,9 +1490,15 @@ impl<'a, K, V> Handle<NodeRef<marker::Mut<'a>, K, V, marker::Internal>, marker::
                         .correct_parent_link();
                 }
 
-                Global.deallocate(right_node.node.cast(), Layout::new::<InternalNode<K, V>>());
+                dealloc(
+                    right_node.node.cast().as_ptr(),
+                    Layout::new::<InternalNode<K, V>>(),
+                );
             } else {
-                Global.deallocate(right_node.node.cast(), Layout::new::<LeafNode<K, V>>());
+                dealloc(
+                    right_node.node.cast().as_ptr(),
+                    Layout::new::<LeafNode<K, V>>(),
+                );
             }
 
             Handle::new_edge(self.node, self.idx)
@@ -1662,7 +1777,7 @@ unsafe fn slice_insert<T>(slice: &mut [T], idx: usize, val: T) {
         slice.as_mut_ptr().add(idx + 1),
         slice.len() - idx,
     );
-    ptr::write(slice.get_unchecked_mut(idx), val);
+    ptr::write(slice.as_mut_ptr().add(idx), val);
 }
 
 unsafe fn slice_remove<T>(slice: &mut [T], idx: usize) -> T {
@@ -1,13 +1,15 @@
 // This is pretty much entirely stolen from TreeSet, since BTreeMap has an identical interface
 // to TreeMap
 
+use crate::TryClone;
 use crate::TryReserveError;
+use crate::{TryExtend, TryFromIterator};
 use core::borrow::Borrow;
 use core::cmp::max;
 use core::cmp::Ordering::{self, Equal, Greater, Less};
 use core::fmt::{self, Debug};
-use core::iter::{FromIterator, FusedIterator, Peekable};
-use core::ops::{BitAnd, BitOr, BitXor, RangeBounds, Sub};
+use core::iter::{FusedIterator, Peekable};
+use core::ops::RangeBounds;
 
 use super::map::{self, BTreeMap, Keys};
 use super::Recover;
@@ -57,8 +59,7 @@ use super::Recover;
 ///     println!("{}", book);
 /// }
 /// ```
-#[derive(Clone, Hash, PartialEq, Eq, Ord, PartialOrd)]
-
+#[derive(Hash, PartialEq, Eq, Ord, PartialOrd)]
 pub struct BTreeSet<T> {
     map: BTreeMap<T, ()>,
 }
@@ -70,7 +71,6 @@ pub struct BTreeSet<T> {
 ///
 /// [`BTreeSet`]: struct.BTreeSet.html
 /// [`iter`]: struct.BTreeSet.html#method.iter
-
 pub struct Iter<'a, T: 'a> {
     iter: Keys<'a, T, ()>,
 }
@@ -88,7 +88,6 @@ impl<T: fmt::Debug> fmt::Debug for Iter<'_, T> {
 ///
 /// [`BTreeSet`]: struct.BTreeSet.html
 /// [`into_iter`]: struct.BTreeSet.html#method.into_iter
-
 #[derive(Debug)]
 pub struct IntoIter<T> {
     iter: map::IntoIter<T, ()>,
@@ -114,7 +113,6 @@ pub struct Range<'a, T: 'a> {
 ///
 /// [`BTreeSet`]: struct.BTreeSet.html
 /// [`difference`]: struct.BTreeSet.html#method.difference
-
 pub struct Difference<'a, T: 'a> {
     inner: DifferenceInner<'a, T>,
 }
@@ -155,7 +153,6 @@ impl<T: fmt::Debug> fmt::Debug for Difference<'_, T> {
 ///
 /// [`BTreeSet`]: struct.BTreeSet.html
 /// [`symmetric_difference`]: struct.BTreeSet.html#method.symmetric_difference
-
 pub struct SymmetricDifference<'a, T: 'a> {
     a: Peekable<Iter<'a, T>>,
     b: Peekable<Iter<'a, T>>,
@@ -177,7 +174,6 @@ impl<T: fmt::Debug> fmt::Debug for SymmetricDifference<'_, T> {
 ///
 /// [`BTreeSet`]: struct.BTreeSet.html
 /// [`intersection`]: struct.BTreeSet.html#method.intersection
-
 pub struct Intersection<'a, T: 'a> {
     inner: IntersectionInner<'a, T>,
 }
@@ -218,7 +214,6 @@ impl<T: fmt::Debug> fmt::Debug for Intersection<'_, T> {
 ///
 /// [`BTreeSet`]: struct.BTreeSet.html
 /// [`union`]: struct.BTreeSet.html#method.union
-
 pub struct Union<'a, T: 'a> {
     a: Peekable<Iter<'a, T>>,
     b: Peekable<Iter<'a, T>>,
@@ -252,7 +247,6 @@ impl<T: Ord> BTreeSet<T> {
     ///
     /// let mut set: BTreeSet<i32> = BTreeSet::new();
     /// ```
-
     #[inline]
     pub fn new() -> BTreeSet<T> {
         BTreeSet {
@@ -282,11 +276,10 @@ impl<T: Ord> BTreeSet<T> {
     /// }
     /// assert_eq!(Some(&5), set.range(4..).next());
     /// ```
-
     #[inline]
-    pub fn range<K: ?Sized, R>(&self, range: R) -> Range<'_, T>
+    pub fn range<K, R>(&self, range: R) -> Range<'_, T>
     where
-        K: Ord,
+        K: Ord + ?Sized,
         T: Borrow<K>,
         R: RangeBounds<K>,
     {
@@ -315,7 +308,6 @@ impl<T: Ord> BTreeSet<T> {
     /// let diff: Vec<_> = a.difference(&b).cloned().collect();
     /// assert_eq!(diff, [1]);
     /// ```
-
     pub fn difference<'a>(&'a self, other: &'a BTreeSet<T>) -> Difference<'a, T> {
         if self.len() > other.len() / ITER_PERFORMANCE_TIPPING_SIZE_DIFF {
             // Self is bigger than or not much smaller than other set.
@@ -358,7 +350,6 @@ impl<T: Ord> BTreeSet<T> {
     /// let sym_diff: Vec<_> = a.symmetric_difference(&b).cloned().collect();
     /// assert_eq!(sym_diff, [1, 3]);
     /// ```
-
     #[inline]
     pub fn symmetric_difference<'a>(
         &'a self,
@@ -390,7 +381,6 @@ impl<T: Ord> BTreeSet<T> {
     /// let intersection: Vec<_> = a.intersection(&b).cloned().collect();
     /// assert_eq!(intersection, [2]);
     /// ```
-
     pub fn intersection<'a>(&'a self, other: &'a BTreeSet<T>) -> Intersection<'a, T> {
         let (small, other) = if self.len() <= other.len() {
             (self, other)
@@ -436,7 +426,6 @@ impl<T: Ord> BTreeSet<T> {
     /// let union: Vec<_> = a.union(&b).cloned().collect();
     /// assert_eq!(union, [1, 2]);
     /// ```
-
     #[inline]
     pub fn union<'a>(&'a self, other: &'a BTreeSet<T>) -> Union<'a, T> {
         Union {
@@ -457,7 +446,6 @@ impl<T: Ord> BTreeSet<T> {
     /// v.clear();
     /// assert!(v.is_empty());
     /// ```
-
     #[inline(always)]
     pub fn clear(&mut self) {
         self.map.clear()
@@ -478,12 +466,11 @@ impl<T: Ord> BTreeSet<T> {
     /// assert_eq!(set.contains(&1), true);
     /// assert_eq!(set.contains(&4), false);
     /// ```
-
     #[inline(always)]
-    pub fn contains<Q: ?Sized>(&self, value: &Q) -> bool
+    pub fn contains<Q>(&self, value: &Q) -> bool
     where
         T: Borrow<Q>,
-        Q: Ord,
+        Q: Ord + ?Sized,
     {
         self.map.contains_key(value)
     }
@@ -503,12 +490,11 @@ impl<T: Ord> BTreeSet<T> {
     /// assert_eq!(set.get(&2), Some(&2));
     /// assert_eq!(set.get(&4), None);
     /// ```
-
     #[inline(always)]
-    pub fn get<Q: ?Sized>(&self, value: &Q) -> Option<&T>
+    pub fn get<Q>(&self, value: &Q) -> Option<&T>
     where
         T: Borrow<Q>,
-        Q: Ord,
+        Q: Ord + ?Sized,
     {
         Recover::get(&self.map, value)
     }
@@ -530,7 +516,6 @@ impl<T: Ord> BTreeSet<T> {
     /// b.insert(1);
     /// assert_eq!(a.is_disjoint(&b), false);
     /// ```
-
     #[inline]
     pub fn is_disjoint(&self, other: &BTreeSet<T>) -> bool {
         self.intersection(other).next().is_none()
@@ -553,7 +538,6 @@ impl<T: Ord> BTreeSet<T> {
     /// set.insert(4);
     /// assert_eq!(set.is_subset(&sup), false);
     /// ```
-
     pub fn is_subset(&self, other: &BTreeSet<T>) -> bool {
         // Same result as self.difference(other).next().is_none()
         // but the 3 paths below are faster (in order: hugely, 20%, 5%).
@@ -615,7 +599,6 @@ impl<T: Ord> BTreeSet<T> {
     /// set.insert(2);
     /// assert_eq!(set.is_superset(&sub), true);
     /// ```
-
     #[inline(always)]
     pub fn is_superset(&self, other: &BTreeSet<T>) -> bool {
         other.is_subset(self)
@@ -641,7 +624,6 @@ impl<T: Ord> BTreeSet<T> {
     /// assert_eq!(set.insert(2), false);
     /// assert_eq!(set.len(), 1);
     /// ```
-
     #[inline]
     pub fn try_insert(&mut self, value: T) -> Result<bool, TryReserveError> {
         Ok(self.map.try_insert(value, ())?.is_none())
@@ -662,10 +644,9 @@ impl<T: Ord> BTreeSet<T> {
     /// set.replace(Vec::with_capacity(10));
     /// assert_eq!(set.get(&[][..]).unwrap().capacity(), 10);
     /// ```
-
     #[inline]
     pub fn replace(&mut self, value: T) -> Result<Option<T>, TryReserveError> {
-        Ok(Recover::replace(&mut self.map, value)?)
+        Recover::replace(&mut self.map, value)
     }
 
     /// Removes a value from the set. Returns whether the value was
@@ -686,12 +667,11 @@ impl<T: Ord> BTreeSet<T> {
     /// assert_eq!(set.remove(&2), true);
     /// assert_eq!(set.remove(&2), false);
     /// ```
-
     #[inline(always)]
-    pub fn remove<Q: ?Sized>(&mut self, value: &Q) -> bool
+    pub fn remove<Q>(&mut self, value: &Q) -> bool
     where
         T: Borrow<Q>,
-        Q: Ord,
+        Q: Ord + ?Sized,
     {
         self.map.remove(value).is_some()
     }
@@ -711,48 +691,40 @@ impl<T: Ord> BTreeSet<T> {
     /// assert_eq!(set.take(&2), Some(2));
     /// assert_eq!(set.take(&2), None);
     /// ```
-
     #[inline(always)]
-    pub fn take<Q: ?Sized>(&mut self, value: &Q) -> Option<T>
+    pub fn take<Q>(&mut self, value: &Q) -> Option<T>
     where
         T: Borrow<Q>,
-        Q: Ord,
+        Q: Ord + ?Sized,
     {
         Recover::take(&mut self.map, value)
     }
 
     /// Moves all elements from `other` into `Self`, leaving `other` empty.
     ///
+    /// If the allocation of the merged tree fails, both sets are left unchanged.
+    ///
     /// # Examples
     ///
     /// ```
-    /// use std::collections::BTreeSet;
+    /// use fallible_collections::btree::BTreeSet;
     ///
     /// let mut a = BTreeSet::new();
-    /// a.insert(1);
-    /// a.insert(2);
-    /// a.insert(3);
+    /// a.try_insert(1).unwrap();
+    /// a.try_insert(2).unwrap();
     ///
     /// let mut b = BTreeSet::new();
-    /// b.insert(3);
-    /// b.insert(4);
-    /// b.insert(5);
+    /// b.try_insert(2).unwrap();
+    /// b.try_insert(3).unwrap();
     ///
-    /// a.append(&mut b);
+    /// a.try_append(&mut b).unwrap();
     ///
-    /// assert_eq!(a.len(), 5);
+    /// assert_eq!(a.len(), 3);
     /// assert_eq!(b.len(), 0);
-    ///
-    /// assert!(a.contains(&1));
-    /// assert!(a.contains(&2));
-    /// assert!(a.contains(&3));
-    /// assert!(a.contains(&4));
-    /// assert!(a.contains(&5));
     /// ```
-
     #[inline(always)]
-    pub fn append(&mut self, other: &mut Self) {
-        self.map.append(&mut other.map);
+    pub fn try_append(&mut self, other: &mut Self) -> Result<(), TryReserveError> {
+        self.map.try_append(&mut other.map)
     }
 
     /// Splits the collection into two at the given key. Returns everything after the given key,
@@ -784,7 +756,6 @@ impl<T: Ord> BTreeSet<T> {
     /// assert!(b.contains(&17));
     /// assert!(b.contains(&41));
     /// ```
-
     #[inline]
     pub fn try_split_off<Q: ?Sized + Ord>(&mut self, key: &Q) -> Result<Self, TryReserveError>
     where
@@ -824,7 +795,6 @@ impl<T> BTreeSet<T> {
     /// assert_eq!(set_iter.next(), Some(&3));
     /// assert_eq!(set_iter.next(), None);
     /// ```
-
     #[inline(always)]
     pub fn iter(&self) -> Iter<'_, T> {
         Iter {
@@ -844,7 +814,6 @@ impl<T> BTreeSet<T> {
     /// v.insert(1);
     /// assert_eq!(v.len(), 1);
     /// ```
-
     #[inline(always)]
     pub fn len(&self) -> usize {
         self.map.len()
@@ -862,22 +831,12 @@ impl<T> BTreeSet<T> {
     /// v.insert(1);
     /// assert!(!v.is_empty());
     /// ```
-
     #[inline(always)]
     pub fn is_empty(&self) -> bool {
         self.len() == 0
     }
 }
 
-impl<T: Ord> FromIterator<T> for BTreeSet<T> {
-    #[inline]
-    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> BTreeSet<T> {
-        let mut set = BTreeSet::new();
-        set.extend(iter);
-        set
-    }
-}
-
 impl<T> IntoIterator for BTreeSet<T> {
     type Item = T;
     type IntoIter = IntoIter<T>;
@@ -912,115 +871,273 @@ impl<'a, T> IntoIterator for &'a BTreeSet<T> {
     }
 }
 
-impl<T: Ord> Extend<T> for BTreeSet<T> {
-    #[inline]
-    fn extend<Iter: IntoIterator<Item = T>>(&mut self, iter: Iter) {
-        iter.into_iter().for_each(move |elem| {
-            self.try_insert(elem).expect("Out of Mem");
-        });
+impl<T: Ord> BTreeSet<T> {
+    /// Creates a set from the elements of an iterator, returning an error
+    /// if the allocation of a node fails.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fallible_collections::btree::BTreeSet;
+    ///
+    /// let set = BTreeSet::try_from_iter(vec![3, 1, 2, 1]).unwrap();
+    /// assert_eq!(set.len(), 3);
+    /// assert!(set.contains(&2));
+    /// ```
+    pub fn try_from_iter<I: IntoIterator<Item = T>>(iter: I) -> Result<Self, TryReserveError> {
+        let mut set = BTreeSet::new();
+        set.try_extend(iter)?;
+        Ok(set)
     }
-}
 
-impl<'a, T: 'a + Ord + Copy> Extend<&'a T> for BTreeSet<T> {
-    #[inline]
-    fn extend<I: IntoIterator<Item = &'a T>>(&mut self, iter: I) {
-        self.extend(iter.into_iter().cloned());
+    /// Inserts the elements of an iterator into the set. On allocation
+    /// failure, the elements inserted so far are kept and the rest of the
+    /// iterator is dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fallible_collections::btree::BTreeSet;
+    ///
+    /// let mut set = BTreeSet::new();
+    /// set.try_extend([1, 2].iter().copied()).unwrap();
+    /// assert_eq!(set.len(), 2);
+    /// ```
+    pub fn try_extend<I: IntoIterator<Item = T>>(
+        &mut self,
+        iter: I,
+    ) -> Result<(), TryReserveError> {
+        for elem in iter {
+            self.try_insert(elem)?;
+        }
+        Ok(())
     }
 }
 
-impl<T: Ord> Default for BTreeSet<T> {
-    /// Makes an empty `BTreeSet<T>` with a reasonable choice of B.
-    #[inline(always)]
-    fn default() -> BTreeSet<T> {
-        BTreeSet::new()
+impl<T: Ord + TryClone> BTreeSet<T> {
+    /// Returns a new set holding a clone of every value in `self` or
+    /// `other`, returning an error if an allocation fails.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fallible_collections::btree::BTreeSet;
+    ///
+    /// let a = BTreeSet::try_from_iter(vec![1, 2, 3]).unwrap();
+    /// let b = BTreeSet::try_from_iter(vec![3, 4, 5]).unwrap();
+    ///
+    /// let union = a.try_union_set(&b).unwrap();
+    /// assert!(union.iter().copied().eq(1..=5));
+    /// ```
+    pub fn try_union_set(&self, other: &BTreeSet<T>) -> Result<BTreeSet<T>, TryReserveError> {
+        Self::try_clone_from_iter(self.union(other))
     }
-}
 
-impl<T: Ord + Clone> Sub<&BTreeSet<T>> for &BTreeSet<T> {
-    type Output = BTreeSet<T>;
-
-    /// Returns the difference of `self` and `rhs` as a new `BTreeSet<T>`.
+    /// Returns a new set holding a clone of every value in both `self` and
+    /// `other`, returning an error if an allocation fails.
     ///
     /// # Examples
     ///
     /// ```
-    /// use std::collections::BTreeSet;
+    /// use fallible_collections::btree::BTreeSet;
     ///
-    /// let a: BTreeSet<_> = vec![1, 2, 3].into_iter().collect();
-    /// let b: BTreeSet<_> = vec![3, 4, 5].into_iter().collect();
+    /// let a = BTreeSet::try_from_iter(vec![1, 2, 3]).unwrap();
+    /// let b = BTreeSet::try_from_iter(vec![2, 3, 4]).unwrap();
     ///
-    /// let result = &a - &b;
-    /// let result_vec: Vec<_> = result.into_iter().collect();
-    /// assert_eq!(result_vec, [1, 2]);
+    /// let intersection = a.try_intersection_set(&b).unwrap();
+    /// assert!(intersection.iter().copied().eq(2..=3));
     /// ```
-    fn sub(self, rhs: &BTreeSet<T>) -> BTreeSet<T> {
-        self.difference(rhs).cloned().collect()
+    pub fn try_intersection_set(
+        &self,
+        other: &BTreeSet<T>,
+    ) -> Result<BTreeSet<T>, TryReserveError> {
+        Self::try_clone_from_iter(self.intersection(other))
     }
-}
 
-impl<T: Ord + Clone> BitXor<&BTreeSet<T>> for &BTreeSet<T> {
-    type Output = BTreeSet<T>;
+    /// Returns a new set holding a clone of every value in `self` but not
+    /// in `other`, returning an error if an allocation fails.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fallible_collections::btree::BTreeSet;
+    ///
+    /// let a = BTreeSet::try_from_iter(vec![1, 2, 3]).unwrap();
+    /// let b = BTreeSet::try_from_iter(vec![3, 4, 5]).unwrap();
+    ///
+    /// let difference = a.try_difference_set(&b).unwrap();
+    /// assert!(difference.iter().copied().eq(1..=2));
+    /// ```
+    pub fn try_difference_set(&self, other: &BTreeSet<T>) -> Result<BTreeSet<T>, TryReserveError> {
+        Self::try_clone_from_iter(self.difference(other))
+    }
 
-    /// Returns the symmetric difference of `self` and `rhs` as a new `BTreeSet<T>`.
+    /// Returns a new set holding a clone of every value in `self` or
+    /// `other` but not in both, returning an error if an allocation fails.
     ///
     /// # Examples
     ///
     /// ```
-    /// use std::collections::BTreeSet;
+    /// use fallible_collections::btree::BTreeSet;
     ///
-    /// let a: BTreeSet<_> = vec![1, 2, 3].into_iter().collect();
-    /// let b: BTreeSet<_> = vec![2, 3, 4].into_iter().collect();
+    /// let a = BTreeSet::try_from_iter(vec![1, 2, 3]).unwrap();
+    /// let b = BTreeSet::try_from_iter(vec![2, 3, 4]).unwrap();
     ///
-    /// let result = &a ^ &b;
-    /// let result_vec: Vec<_> = result.into_iter().collect();
-    /// assert_eq!(result_vec, [1, 4]);
+    /// let symmetric_difference = a.try_symmetric_difference_set(&b).unwrap();
+    /// assert!(symmetric_difference.iter().copied().eq(vec![1, 4]));
     /// ```
-    fn bitxor(self, rhs: &BTreeSet<T>) -> BTreeSet<T> {
-        self.symmetric_difference(rhs).cloned().collect()
+    pub fn try_symmetric_difference_set(
+        &self,
+        other: &BTreeSet<T>,
+    ) -> Result<BTreeSet<T>, TryReserveError> {
+        Self::try_clone_from_iter(self.symmetric_difference(other))
     }
-}
 
-impl<T: Ord + Clone> BitAnd<&BTreeSet<T>> for &BTreeSet<T> {
-    type Output = BTreeSet<T>;
+    /// Adds a clone of every value of `other` to `self`.
+    ///
+    /// The missing values are cloned and the merged tree is allocated before
+    /// `self` is touched, so if an allocation fails, `self` is left unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fallible_collections::btree::BTreeSet;
+    ///
+    /// let mut a = BTreeSet::try_from_iter(vec![1, 2, 3]).unwrap();
+    /// let b = BTreeSet::try_from_iter(vec![3, 4, 5]).unwrap();
+    ///
+    /// a.try_union_with(&b).unwrap();
+    /// assert!(a.iter().copied().eq(1..=5));
+    /// ```
+    pub fn try_union_with(&mut self, other: &BTreeSet<T>) -> Result<(), TryReserveError> {
+        let mut missing = other.try_difference_set(self)?;
+        self.try_append(&mut missing)
+    }
 
-    /// Returns the intersection of `self` and `rhs` as a new `BTreeSet<T>`.
+    /// Keeps only the values that are in exactly one of `self` and `other`.
+    ///
+    /// Only the values of `other` missing from `self` are cloned. They and the
+    /// nodes of the merged tree are allocated before `self` is touched, so if
+    /// an allocation fails, `self` is left unchanged.
     ///
     /// # Examples
     ///
     /// ```
-    /// use std::collections::BTreeSet;
+    /// use fallible_collections::btree::BTreeSet;
     ///
-    /// let a: BTreeSet<_> = vec![1, 2, 3].into_iter().collect();
-    /// let b: BTreeSet<_> = vec![2, 3, 4].into_iter().collect();
+    /// let mut a = BTreeSet::try_from_iter(vec![1, 2, 3]).unwrap();
+    /// let b = BTreeSet::try_from_iter(vec![2, 3, 4]).unwrap();
     ///
-    /// let result = &a & &b;
-    /// let result_vec: Vec<_> = result.into_iter().collect();
-    /// assert_eq!(result_vec, [2, 3]);
+    /// a.try_symmetric_difference_with(&b).unwrap();
+    /// assert!(a.iter().copied().eq(vec![1, 4]));
     /// ```
-    fn bitand(self, rhs: &BTreeSet<T>) -> BTreeSet<T> {
-        self.intersection(rhs).cloned().collect()
+    pub fn try_symmetric_difference_with(
+        &mut self,
+        other: &BTreeSet<T>,
+    ) -> Result<(), TryReserveError> {
+        let mut missing = other.try_difference_set(self)?;
+        let common = other.len() - missing.len();
+        let mut pool = map::sorted_iter_node_pool(self.len() - common + missing.len())?;
+        self.map.retain(|value, _| !other.contains(value));
+        self.map.append_in(&mut missing.map, &mut pool);
+        Ok(())
+    }
+
+    fn try_clone_from_iter<'a, I>(iter: I) -> Result<BTreeSet<T>, TryReserveError>
+    where
+        I: Iterator<Item = &'a T>,
+        T: 'a,
+    {
+        let mut set = BTreeSet::new();
+        for value in iter {
+            set.try_insert(value.try_clone()?)?;
+        }
+        Ok(set)
     }
 }
 
-impl<T: Ord + Clone> BitOr<&BTreeSet<T>> for &BTreeSet<T> {
-    type Output = BTreeSet<T>;
+impl<T: Ord> BTreeSet<T> {
+    /// Removes every value of `other` from `self`.
+    ///
+    /// Removing values never allocates, so unlike the other in-place set
+    /// operations this one cannot fail.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fallible_collections::btree::BTreeSet;
+    ///
+    /// let mut a = BTreeSet::try_from_iter(vec![1, 2, 3]).unwrap();
+    /// let b = BTreeSet::try_from_iter(vec![3, 4, 5]).unwrap();
+    ///
+    /// a.difference_with(&b);
+    /// assert!(a.iter().copied().eq(1..=2));
+    /// ```
+    pub fn difference_with(&mut self, other: &BTreeSet<T>) {
+        for value in other {
+            self.remove(value);
+        }
+    }
 
-    /// Returns the union of `self` and `rhs` as a new `BTreeSet<T>`.
+    /// Keeps only the values of `self` that are also in `other`.
+    ///
+    /// The other values are removed in place, which never allocates, so
+    /// this cannot fail.
     ///
     /// # Examples
     ///
     /// ```
-    /// use std::collections::BTreeSet;
+    /// use fallible_collections::btree::BTreeSet;
     ///
-    /// let a: BTreeSet<_> = vec![1, 2, 3].into_iter().collect();
-    /// let b: BTreeSet<_> = vec![3, 4, 5].into_iter().collect();
+    /// let mut a = BTreeSet::try_from_iter(vec![1, 2, 3]).unwrap();
+    /// let b = BTreeSet::try_from_iter(vec![2, 3, 4]).unwrap();
     ///
-    /// let result = &a | &b;
-    /// let result_vec: Vec<_> = result.into_iter().collect();
-    /// assert_eq!(result_vec, [1, 2, 3, 4, 5]);
+    /// a.intersection_with(&b);
+    /// assert!(a.iter().copied().eq(2..=3));
     /// ```
-    fn bitor(self, rhs: &BTreeSet<T>) -> BTreeSet<T> {
-        self.union(rhs).cloned().collect()
+    pub fn intersection_with(&mut self, other: &BTreeSet<T>) {
+        self.map.retain(|value, _| other.contains(value));
+    }
+}
+
+impl<T: Ord> TryExtend<T> for BTreeSet<T> {
+    #[inline(always)]
+    fn try_extend<I: IntoIterator<Item = T>>(&mut self, iter: I) -> Result<(), TryReserveError> {
+        BTreeSet::try_extend(self, iter)
+    }
+}
+
+impl<'a, T: 'a + Ord + Copy> TryExtend<&'a T> for BTreeSet<T> {
+    #[inline]
+    fn try_extend<I: IntoIterator<Item = &'a T>>(
+        &mut self,
+        iter: I,
+    ) -> Result<(), TryReserveError> {
+        BTreeSet::try_extend(self, iter.into_iter().copied())
+    }
+}
+
+impl<T: Ord> TryFromIterator<T> for BTreeSet<T> {
+    #[inline]
+    fn try_from_iterator<I: IntoIterator<Item = T>>(iterator: I) -> Result<Self, TryReserveError> {
+        BTreeSet::try_from_iter(iterator)
+    }
+}
+
+impl<T: TryClone> TryClone for BTreeSet<T> {
+    #[inline]
+    fn try_clone(&self) -> Result<Self, TryReserveError> {
+        Ok(BTreeSet {
+            map: self.map.try_clone()?,
+        })
+    }
+}
+
+impl<T: Ord> Default for BTreeSet<T> {
+    /// Makes an empty `BTreeSet<T>` with a reasonable choice of B.
+    #[inline(always)]
+    fn default() -> BTreeSet<T> {
+        BTreeSet::new()
     }
 }
 
@@ -1189,7 +1306,7 @@ impl<'a, T: Ord> Iterator for Difference<'a, T> {
                 other_set,
             } => loop {
                 let self_next = self_iter.next()?;
-                if !other_set.contains(&self_next) {
+                if !other_set.contains(self_next) {
                     return Some(self_next);
                 }
             },
@@ -1293,7 +1410,7 @@ impl<'a, T: Ord> Iterator for Intersection<'a, T> {
                 large_set,
             } => loop {
                 let small_next = small_iter.next()?;
-                if large_set.contains(&small_next) {
+                if large_set.contains(small_next) {
                     return Some(small_next);
                 }
             },
@@ -1344,3 +1461,85 @@ impl<'a, T: Ord> Iterator for Union<'a, T> {
 }
 
 impl<T: Ord> FusedIterator for Union<'_, T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn try_insert_and_split_off() {
+        let mut set = BTreeSet::new();
+        for i in (0..200).rev() {
+            assert!(set.try_insert(i).unwrap());
+        }
+        assert!(!set.try_insert(0).unwrap());
+        let right = set.try_split_off(&150).unwrap();
+        assert!(set.iter().copied().eq(0..150));
+        assert!(right.iter().copied().eq(150..200));
+        assert!(set.remove(&3));
+        assert!(!set.contains(&3));
+    }
+
+    #[test]
+    fn try_extend_and_clone() {
+        let mut set = BTreeSet::try_from_iter(0..100).unwrap();
+        set.try_extend(50..150).unwrap();
+        assert_eq!(set.len(), 150);
+        let clone = set.try_clone().unwrap();
+        assert!(clone.iter().eq(set.iter()));
+    }
+
+    #[test]
+    fn try_set_algebra() {
+        let a = BTreeSet::try_from_iter((0..300).filter(|i| i % 2 == 0)).unwrap();
+        let b = BTreeSet::try_from_iter((0..300).filter(|i| i % 3 == 0)).unwrap();
+
+        let union = a.try_union_set(&b).unwrap();
+        assert!(union.iter().eq(a.union(&b)));
+        let intersection = a.try_intersection_set(&b).unwrap();
+        assert!(intersection.iter().eq(a.intersection(&b)));
+        let difference = a.try_difference_set(&b).unwrap();
+        assert!(difference.iter().eq(a.difference(&b)));
+        let symmetric_difference = a.try_symmetric_difference_set(&b).unwrap();
+        assert!(symmetric_difference.iter().eq(a.symmetric_difference(&b)));
+
+        let mut c = a.try_clone().unwrap();
+        c.try_union_with(&b).unwrap();
+        assert!(c.iter().eq(union.iter()));
+        let mut c = a.try_clone().unwrap();
+        // removing in place doesn't need any allocation
+        crate::test_alloc::with_alloc_budget(0, || c.intersection_with(&b));
+        assert!(c.iter().eq(intersection.iter()));
+        let mut c = a.try_clone().unwrap();
+        c.difference_with(&b);
+        assert!(c.iter().eq(difference.iter()));
+        let mut c = a.try_clone().unwrap();
+        c.try_symmetric_difference_with(&b).unwrap();
+        assert!(c.iter().eq(symmetric_difference.iter()));
+    }
+
+    #[test]
+    fn try_symmetric_difference_with_oom() {
+        use crate::test_alloc::with_alloc_budget;
+
+        let a = BTreeSet::try_from_iter((0..300).filter(|i| i % 2 == 0)).unwrap();
+        let b = BTreeSet::try_from_iter((0..300).filter(|i| i % 3 == 0)).unwrap();
+        let mut c = a.try_clone().unwrap();
+        // every allocation failing on the way leaves `c` as it was
+        let mut budget = 0;
+        while with_alloc_budget(budget, || c.try_symmetric_difference_with(&b)).is_err() {
+            assert!(c.iter().eq(a.iter()));
+            budget += 1;
+        }
+        assert!(budget > 0);
+        assert!(c.iter().eq(a.symmetric_difference(&b)));
+    }
+
+    #[test]
+    fn try_collect() {
+        use crate::TryCollect;
+
+        let set: BTreeSet<_> = vec![3, 1, 2, 1].try_collect().unwrap();
+        assert!(set.iter().copied().eq(1..=3));
+    }
+}
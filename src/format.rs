@@ -1,40 +1,259 @@
 //! A try_format! macro replacing format!
-use super::FallibleVec;
+use crate::FallibleString;
 use crate::TryReserveError;
-use alloc::fmt::{Arguments, Write};
+use alloc::fmt::{self, Arguments};
 use alloc::string::String;
 
-/// Take a max capacity a try allocating a string with it.
+/// fmt::Write sink growing the string fallibly. As fmt::Error can't
+/// carry the cause, the allocation error is kept aside to be returned
+/// once formatting stopped.
+struct FallibleWriter<'a> {
+    s: &'a mut String,
+    error: Option<TryReserveError>,
+}
+
+impl fmt::Write for FallibleWriter<'_> {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        match self.s.try_push_str(s) {
+            Ok(()) => Ok(()),
+            Err(e) => {
+                self.error = Some(e);
+                Err(fmt::Error)
+            }
+        }
+    }
+
+    #[inline]
+    fn write_char(&mut self, c: char) -> fmt::Result {
+        match self.s.try_push(c) {
+            Ok(()) => Ok(()),
+            Err(e) => {
+                self.error = Some(e);
+                Err(fmt::Error)
+            }
+        }
+    }
+}
+
+/// Format `args` on `s`, returning an error instead of aborting if the
+/// string can't grow.
 ///
-/// # Warning:
+/// # Panics
 ///
-/// the max capacity must be > to the formating of the
-/// arguments. If writing the argument on the string exceed the
-/// capacity, no error is return and an allocation can occurs which
-/// can lead to a panic
-pub fn try_format(max_capacity: usize, args: Arguments<'_>) -> Result<String, TryReserveError> {
-    let v = FallibleVec::try_with_capacity(max_capacity)?;
-    let mut s = String::from_utf8(v).expect("wtf an empty vec should be valid utf8");
-    s.write_fmt(args)
-        .expect("a formatting trait implementation returned an error");
+/// Like `format!`, panics if a formatting trait implementation returns
+/// an error on its own.
+fn try_write_fmt(s: &mut String, args: Arguments<'_>) -> Result<(), TryReserveError> {
+    let mut writer = FallibleWriter { s, error: None };
+    if fmt::write(&mut writer, args).is_err() {
+        match writer.error {
+            Some(e) => return Err(e),
+            None => panic!("a formatting trait implementation returned an error"),
+        }
+    }
+    Ok(())
+}
+
+/// Try allocating a string with `capacity` and formatting the arguments
+/// on it. The capacity is only a hint: if the formatted arguments don't
+/// fit, the string grows fallibly and an error is returned if it can't.
+pub fn try_format(capacity: usize, args: Arguments<'_>) -> Result<String, TryReserveError> {
+    let mut s = <String as FallibleString>::try_with_capacity(capacity)?;
+    try_write_fmt(&mut s, args)?;
     Ok(s)
 }
 
-#[macro_export]
-/// Take a max capacity a try allocating a string with it.
+/// fmt::Write sink only counting the length of the formatted arguments
+struct LenCounter {
+    len: usize,
+}
+
+impl fmt::Write for LenCounter {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        // an overflowing length can't be allocated anyway
+        self.len = self.len.saturating_add(s.len());
+        Ok(())
+    }
+}
+
+/// Same as `try_format`, but formats the arguments a first time to get
+/// their exact length, so the string is allocated once without any
+/// over-reservation. The formatting trait implementations are thus
+/// called twice.
+///
+/// # Panics
+///
+/// Like `format!`, panics if a formatting trait implementation returns
+/// an error on its own.
+pub fn try_format_exact(args: Arguments<'_>) -> Result<String, TryReserveError> {
+    let mut counter = LenCounter { len: 0 };
+    fmt::write(&mut counter, args).expect("a formatting trait implementation returned an error");
+    // if an implementation writes more the second time, the string
+    // still grows fallibly
+    try_format(counter.len, args)
+}
+
+/// Error returned by `try_format_into` when the formatted arguments
+/// don't fit in the buffer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FormatOverflow {
+    /// number of bytes the formatted arguments needed
+    pub needed: usize,
+}
+
+impl fmt::Display for FormatOverflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "formatting needed a buffer of {} bytes", self.needed)
+    }
+}
+
+/// fmt::Write sink copying into a fixed buffer. Once a write doesn't
+/// fit, nothing more is copied but the needed length is still counted.
+struct BufWriter<'a> {
+    buf: &'a mut [u8],
+    len: usize,
+    needed: usize,
+}
+
+impl fmt::Write for BufWriter<'_> {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.needed = self.needed.saturating_add(s.len());
+        if self.needed <= self.buf.len() {
+            self.buf[self.len..self.needed].copy_from_slice(s.as_bytes());
+            self.len = self.needed;
+        }
+        Ok(())
+    }
+}
+
+/// Format the arguments into `buf` without any allocation, returning the
+/// formatted str. If `buf` is too small, the error tells how many bytes
+/// were needed.
 ///
-/// # Warning:
+/// # Panics
 ///
-/// the max capacity must be > to the formating of the
-/// arguments. If writing the argument on the string exceed the
-/// capacity, no error is return and an allocation can occurs which
-/// can lead to a panic
+/// Like `format!`, panics if a formatting trait implementation returns
+/// an error on its own.
+pub fn try_format_into<'a>(
+    buf: &'a mut [u8],
+    args: Arguments<'_>,
+) -> Result<&'a str, FormatOverflow> {
+    let mut writer = BufWriter {
+        buf,
+        len: 0,
+        needed: 0,
+    };
+    fmt::write(&mut writer, args).expect("a formatting trait implementation returned an error");
+    if writer.needed > writer.len {
+        return Err(FormatOverflow {
+            needed: writer.needed,
+        });
+    }
+    let BufWriter { buf, len, .. } = writer;
+    // only whole strs were copied, so the bytes are utf8
+    Ok(unsafe { core::str::from_utf8_unchecked(&buf[..len]) })
+}
+
+/// Fallible version of `ToString`, implemented for every `Display` type
+/// # Examples
+///
+/// ```
+/// use fallible_collections::format::TryToString;
+///
+/// assert_eq!(42.try_to_string().unwrap(), "42");
+/// assert_eq!("str".try_to_string().unwrap(), "str");
+/// ```
+pub trait TryToString {
+    fn try_to_string(&self) -> Result<String, TryReserveError>;
+}
+
+#[cfg(not(feature = "unstable"))]
+impl<T: fmt::Display + ?Sized> TryToString for T {
+    #[inline]
+    fn try_to_string(&self) -> Result<String, TryReserveError> {
+        try_format(0, format_args!("{}", self))
+    }
+}
+
+#[cfg(feature = "unstable")]
+impl<T: fmt::Display + ?Sized> TryToString for T {
+    #[inline]
+    default fn try_to_string(&self) -> Result<String, TryReserveError> {
+        try_format(0, format_args!("{}", self))
+    }
+}
+
+#[cfg(feature = "unstable")]
+impl TryToString for str {
+    #[inline]
+    fn try_to_string(&self) -> Result<String, TryReserveError> {
+        let mut s = <String as FallibleString>::try_with_capacity(self.len())?;
+        s.push_str(self);
+        Ok(s)
+    }
+}
+
+#[cfg(feature = "unstable")]
+impl TryToString for char {
+    #[inline]
+    fn try_to_string(&self) -> Result<String, TryReserveError> {
+        let mut s = <String as FallibleString>::try_with_capacity(self.len_utf8())?;
+        s.push(*self);
+        Ok(s)
+    }
+}
+
+/// integers are formatted on the stack, then allocated once with their
+/// exact length
+#[cfg(feature = "unstable")]
+macro_rules! impl_try_to_string_int {
+    ($($t: ty),*) => {
+        $(impl TryToString for $t {
+            #[inline]
+            fn try_to_string(&self) -> Result<String, TryReserveError> {
+                // enough for i128::MIN
+                let mut buf = [0u8; 40];
+                let digits = try_format_into(&mut buf, format_args!("{}", self))
+                    .expect("an integer fits in 40 bytes");
+                digits.try_to_string()
+            }
+        }
+        )*
+    }
+}
+
+#[cfg(feature = "unstable")]
+impl_try_to_string_int!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+#[macro_export]
+/// Fallible version of format!, taking a capacity hint for the string.
+/// See `try_format`.
 macro_rules! tryformat {
     ($max_capacity:tt, $($arg:tt)*) => (
         $crate::format::try_format($max_capacity, format_args!($($arg)*))
     )
 }
 
+#[macro_export]
+/// Fallible version of format!, allocating the exact length of the
+/// formatted string. See `try_format_exact`.
+macro_rules! tryformat_exact {
+    ($($arg:tt)*) => (
+        $crate::format::try_format_exact(format_args!($($arg)*))
+    )
+}
+
+#[macro_export]
+/// Format the arguments into a byte buffer without allocating.
+/// See `try_format_into`.
+macro_rules! write_into {
+    ($buf:expr, $($arg:tt)*) => (
+        $crate::format::try_format_into($buf, format_args!($($arg)*))
+    )
+}
+
 #[cfg(test)]
 mod tests {
     #[test]
@@ -43,4 +262,63 @@ mod tests {
         assert_eq!(tryformat!(1, "{}", 1).unwrap(), format!("{}", 1));
         assert_eq!(tryformat!(3, "{}", 123).unwrap(), format!("{}", 123));
     }
+
+    #[test]
+    fn format_exceeding_capacity() {
+        let long = "x".repeat(100);
+        assert_eq!(
+            tryformat!(0, "{}-{}", long, 'é').unwrap(),
+            format!("{}-é", long)
+        );
+    }
+
+    #[test]
+    fn format_exact() {
+        let s = tryformat_exact!("{}-{:>5}-{}", 12, 'é', "abc").unwrap();
+        assert_eq!(s, "12-    é-abc");
+        assert_eq!(s.capacity(), s.len());
+        let s = tryformat_exact!("").unwrap();
+        assert_eq!(s.capacity(), 0);
+    }
+
+    #[test]
+    fn format_into() {
+        use super::FormatOverflow;
+
+        let mut buf = [0u8; 8];
+        assert_eq!(write_into!(&mut buf, "{}-{}", 12, 'é').unwrap(), "12-é");
+        assert_eq!(write_into!(&mut buf, "{}", "12345678").unwrap(), "12345678");
+        assert_eq!(
+            write_into!(&mut buf, "{}é{}", "1234567", 1),
+            Err(FormatOverflow { needed: 10 })
+        );
+        assert_eq!(write_into!(&mut [], "").unwrap(), "");
+    }
+
+    #[test]
+    fn try_to_string() {
+        use super::TryToString;
+
+        assert_eq!("héllo".try_to_string().unwrap(), "héllo");
+        assert_eq!('é'.try_to_string().unwrap(), "é");
+        assert_eq!(i128::MIN.try_to_string().unwrap(), i128::MIN.to_string());
+        assert_eq!(u64::MAX.try_to_string().unwrap(), u64::MAX.to_string());
+        assert_eq!((-7i8).try_to_string().unwrap(), "-7");
+        assert_eq!(1.5f64.try_to_string().unwrap(), "1.5");
+        assert_eq!(String::from("s").try_to_string().unwrap(), "s");
+    }
+
+    #[test]
+    #[should_panic(expected = "a formatting trait implementation returned an error")]
+    fn format_trait_error() {
+        use alloc::fmt;
+
+        struct Failing;
+        impl fmt::Display for Failing {
+            fn fmt(&self, _: &mut fmt::Formatter<'_>) -> fmt::Result {
+                Err(fmt::Error)
+            }
+        }
+        let _ = tryformat!(1, "{}", Failing);
+    }
 }
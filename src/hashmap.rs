@@ -1,28 +1,44 @@
 //! Implement Fallible HashMap
 use super::TryClone;
 use crate::TryReserveError;
+use crate::{TryExtend, TryFromIterator};
 use core::borrow::Borrow;
 use core::default::Default;
 use core::fmt::Debug;
-use core::hash::Hash;
+use core::hash::{BuildHasher, Hash};
+use core::marker::PhantomData;
 
 #[cfg(not(all(feature = "std", feature = "rust_1_57")))]
-type HashMap<K, V> = hashbrown::hash_map::HashMap<K, V>;
+type HashMap<K, V, S> = hashbrown::hash_map::HashMap<K, V, S>;
 
 #[cfg(all(feature = "std", feature = "rust_1_57"))]
-type HashMap<K, V> = std::collections::HashMap<K, V>;
+type HashMap<K, V, S> = std::collections::HashMap<K, V, S>;
 
+/// The default hasher of `TryHashMap`, which is the one of the map it wraps
 #[cfg(not(all(feature = "std", feature = "rust_1_57")))]
-use hashbrown::hash_map::{Iter, IntoIter};
+pub type DefaultHashBuilder = hashbrown::hash_map::DefaultHashBuilder;
 
+/// The default hasher of `TryHashMap`, which is the one of the map it wraps
 #[cfg(all(feature = "std", feature = "rust_1_57"))]
-use std::collections::hash_map::{Iter, IntoIter};
+pub type DefaultHashBuilder = std::collections::hash_map::RandomState;
 
-pub struct TryHashMap<K, V> {
-    inner: HashMap<K, V>,
+#[cfg(not(all(feature = "std", feature = "rust_1_57")))]
+use hashbrown::hash_map::{Drain, IntoIter, Iter, IterMut, Keys, Values, ValuesMut};
+
+#[cfg(all(feature = "std", feature = "rust_1_57"))]
+use std::collections::hash_map::{Drain, IntoIter, Iter, IterMut, Keys, Values, ValuesMut};
+
+#[cfg(not(all(feature = "std", feature = "rust_1_57")))]
+use hashbrown::hash_map::Entry as InnerEntry;
+
+#[cfg(all(feature = "std", feature = "rust_1_57"))]
+use std::collections::hash_map::Entry as InnerEntry;
+
+pub struct TryHashMap<K, V, S = DefaultHashBuilder> {
+    inner: HashMap<K, V, S>,
 }
 
-impl<K, V> Default for TryHashMap<K, V> {
+impl<K, V, S: Default> Default for TryHashMap<K, V, S> {
     #[inline(always)]
     fn default() -> Self {
         Self {
@@ -31,67 +47,273 @@ impl<K, V> Default for TryHashMap<K, V> {
     }
 }
 
-impl<K: Debug, V: Debug> Debug for TryHashMap<K, V> {
+impl<K: Debug, V: Debug, S> Debug for TryHashMap<K, V, S> {
     #[inline]
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         self.inner.fmt(f)
     }
 }
 
+impl<K, V, S> TryHashMap<K, V, S> {
+    #[inline(always)]
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        self.inner.iter()
+    }
+
+    #[inline(always)]
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+        self.inner.iter_mut()
+    }
+
+    #[inline(always)]
+    pub fn keys(&self) -> Keys<'_, K, V> {
+        self.inner.keys()
+    }
+
+    #[inline(always)]
+    pub fn values(&self) -> Values<'_, K, V> {
+        self.inner.values()
+    }
+
+    #[inline(always)]
+    pub fn values_mut(&mut self) -> ValuesMut<'_, K, V> {
+        self.inner.values_mut()
+    }
+
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    #[inline(always)]
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Returns a reference to the map's hasher.
+    #[inline(always)]
+    pub fn hasher(&self) -> &S {
+        self.inner.hasher()
+    }
+
+    /// Returns the number of elements the map can hold without allocating.
+    #[inline(always)]
+    pub fn capacity(&self) -> usize {
+        self.inner.capacity()
+    }
+
+    /// Removes all the elements, keeping the allocated memory for reuse.
+    #[inline(always)]
+    pub fn clear(&mut self) {
+        self.inner.clear()
+    }
+
+    /// Removes all the elements and returns them through an iterator,
+    /// keeping the allocated memory for reuse.
+    #[inline(always)]
+    pub fn drain(&mut self) -> Drain<'_, K, V> {
+        self.inner.drain()
+    }
+
+    /// Keeps only the elements for which `f` returns true.
+    #[inline]
+    pub fn retain<F>(&mut self, f: F)
+    where
+        F: FnMut(&K, &mut V) -> bool,
+    {
+        self.inner.retain(f)
+    }
+}
+
 impl<K, V> TryHashMap<K, V>
 where
     K: Eq + Hash,
 {
+    /// Creates an empty map with the default hasher, this does not allocate.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
     #[inline]
     pub fn with_capacity(capacity: usize) -> Result<Self, TryReserveError> {
+        Self::try_with_capacity_and_hasher(capacity, DefaultHashBuilder::default())
+    }
+}
+
+impl<K, V, S> TryHashMap<K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    /// Creates an empty map which will use `hash_builder` to hash the
+    /// keys. It does not allocate.
+    #[inline]
+    pub fn try_with_hasher(hash_builder: S) -> Result<Self, TryReserveError> {
+        Self::try_with_capacity_and_hasher(0, hash_builder)
+    }
+
+    /// Creates a map that can hold `capacity` elements without allocating,
+    /// using `hash_builder` to hash the keys.
+    #[inline]
+    pub fn try_with_capacity_and_hasher(
+        capacity: usize,
+        hash_builder: S,
+    ) -> Result<Self, TryReserveError> {
         let mut map = Self {
-            inner: HashMap::new(),
+            inner: HashMap::with_hasher(hash_builder),
         };
-        map.reserve(capacity)?;
+        map.try_reserve(capacity)?;
         Ok(map)
     }
 
     #[inline(always)]
-    pub fn get<Q: ?Sized>(&self, k: &Q) -> Option<&V>
+    pub fn get<Q>(&self, k: &Q) -> Option<&V>
     where
         K: Borrow<Q>,
-        Q: Hash + Eq,
+        Q: Hash + Eq + ?Sized,
     {
         self.inner.get(k)
     }
 
+    #[inline(always)]
+    pub fn get_key_value<Q>(&self, k: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.inner.get_key_value(k)
+    }
+
+    #[inline(always)]
+    pub fn get_mut<Q>(&mut self, k: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.inner.get_mut(k)
+    }
+
+    #[inline(always)]
+    pub fn contains_key<Q>(&self, k: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.inner.contains_key(k)
+    }
+
     #[inline]
     pub fn insert(&mut self, k: K, v: V) -> Result<Option<V>, TryReserveError> {
-        self.reserve(if self.inner.capacity() == 0 { 4 } else { 1 })?;
+        self.try_reserve(if self.inner.capacity() == 0 { 4 } else { 1 })?;
         Ok(self.inner.insert(k, v))
     }
 
-    #[inline(always)]
-    pub fn iter(&self) -> Iter<'_, K, V> {
-        self.inner.iter()
+    /// Inserts a key-value pair only if it does not require to allocate,
+    /// that is if the key is already present or the map has spare
+    /// capacity. Otherwise the pair is handed back.
+    #[inline]
+    pub fn try_insert_within_capacity(&mut self, k: K, v: V) -> Result<Option<V>, (K, V)> {
+        if self.inner.len() < self.inner.capacity() {
+            return Ok(self.inner.insert(k, v));
+        }
+        match self.inner.get_mut(&k) {
+            Some(old) => Ok(Some(core::mem::replace(old, v))),
+            None => Err((k, v)),
+        }
     }
 
-    #[inline(always)]
-    pub fn len(&self) -> usize {
-        self.inner.len()
+    /// Gets the given key's entry in the map for in-place manipulation.
+    ///
+    /// Room for one more element is reserved before the entry is returned,
+    /// so inserting into a vacant entry never fails.
+    #[inline]
+    pub fn try_entry(&mut self, key: K) -> Result<Entry<'_, K, V, S>, TryReserveError> {
+        self.try_reserve(if self.inner.capacity() == 0 { 4 } else { 1 })?;
+        Ok(match self.inner.entry(key) {
+            InnerEntry::Occupied(inner) => Entry::Occupied(OccupiedEntry {
+                inner,
+                hasher: PhantomData,
+            }),
+            InnerEntry::Vacant(inner) => Entry::Vacant(VacantEntry {
+                inner,
+                hasher: PhantomData,
+            }),
+        })
     }
 
     #[inline(always)]
-    pub fn remove<Q: ?Sized>(&mut self, k: &Q) -> Option<V>
+    pub fn remove<Q>(&mut self, k: &Q) -> Option<V>
     where
         K: Borrow<Q>,
-        Q: Hash + Eq,
+        Q: Hash + Eq + ?Sized,
     {
         self.inner.remove(k)
     }
 
     #[inline(always)]
-    fn reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
-        #[cfg(not(all(feature = "std", feature = "rust_1_57")))]
+    pub fn remove_entry<Q>(&mut self, k: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.inner.remove_entry(k)
+    }
+
+    /// Shrinks the capacity of the map as much as possible, but no lower
+    /// than `min_capacity`.
+    ///
+    /// The elements are moved to a smaller table, which is allocated
+    /// first: if that allocation fails, the map is left unchanged.
+    pub fn try_shrink_to(&mut self, min_capacity: usize) -> Result<(), TryReserveError>
+    where
+        S: Clone,
+    {
+        let min_capacity = self.len().max(min_capacity);
+        if self.inner.capacity() <= min_capacity {
+            return Ok(());
+        }
+        let mut shrunk =
+            Self::try_with_capacity_and_hasher(min_capacity, self.inner.hasher().clone())?;
+        core::mem::swap(self, &mut shrunk);
+        for (k, v) in shrunk.inner {
+            // the room was reserved above, this cannot allocate
+            self.inner.insert(k, v);
+        }
+        Ok(())
+    }
+
+    /// Shrinks the capacity of the map as much as possible. Shrinking is
+    /// best effort: if the smaller table cannot be allocated, the map is
+    /// left unchanged.
+    #[inline]
+    pub fn shrink_to_fit(&mut self)
+    where
+        S: Clone,
+    {
+        let _ = self.try_shrink_to(0);
+    }
+
+    /// Reserves room for at least `additional` more elements, so that as
+    /// many insertions of new keys will not allocate.
+    #[inline(always)]
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        #[cfg(not(any(
+            all(feature = "std", feature = "rust_1_57"),
+            all(feature = "unstable", not(feature = "rust_1_57"))
+        )))]
         {
             self.inner.try_reserve(additional)
         }
 
+        #[cfg(all(feature = "unstable", not(feature = "rust_1_57")))]
+        {
+            // TryReserveError is the one of alloc here, not the one of hashbrown
+            self.inner
+                .try_reserve(additional)
+                .map_err(from_hashbrown_error)
+        }
+
         #[cfg(all(feature = "std", feature = "rust_1_57"))]
         {
             self.inner.try_reserve(additional).map_err(|_| {
@@ -104,7 +326,207 @@ where
     }
 }
 
-impl<K, V> IntoIterator for TryHashMap<K, V> {
+#[cfg(all(feature = "unstable", not(feature = "rust_1_57")))]
+fn from_hashbrown_error(e: hashbrown::TryReserveError) -> TryReserveError {
+    use alloc::collections::TryReserveErrorKind;
+
+    match e {
+        hashbrown::TryReserveError::CapacityOverflow => TryReserveErrorKind::CapacityOverflow,
+        hashbrown::TryReserveError::AllocError { layout } => TryReserveErrorKind::AllocError {
+            layout,
+            non_exhaustive: (),
+        },
+    }
+    .into()
+}
+
+/// A view into a single entry of a `TryHashMap`, which may either be
+/// vacant or occupied.
+///
+/// It is constructed by `TryHashMap::try_entry`, which already reserved
+/// the room needed to fill a vacant entry. The `or_try_*` methods keep the
+/// signatures of the btree `Entry`, but never fail.
+pub enum Entry<'a, K, V, S = DefaultHashBuilder> {
+    /// An occupied entry.
+    Occupied(OccupiedEntry<'a, K, V, S>),
+
+    /// A vacant entry.
+    Vacant(VacantEntry<'a, K, V, S>),
+}
+
+/// A view into an occupied entry of a `TryHashMap`, part of the `Entry` enum.
+pub struct OccupiedEntry<'a, K, V, S = DefaultHashBuilder> {
+    #[cfg(not(all(feature = "std", feature = "rust_1_57")))]
+    inner: hashbrown::hash_map::OccupiedEntry<'a, K, V, S>,
+    // the entries of std do not depend on the hasher
+    #[cfg(all(feature = "std", feature = "rust_1_57"))]
+    inner: std::collections::hash_map::OccupiedEntry<'a, K, V>,
+    hasher: PhantomData<&'a S>,
+}
+
+/// A view into a vacant entry of a `TryHashMap`, part of the `Entry` enum.
+pub struct VacantEntry<'a, K, V, S = DefaultHashBuilder> {
+    #[cfg(not(all(feature = "std", feature = "rust_1_57")))]
+    inner: hashbrown::hash_map::VacantEntry<'a, K, V, S>,
+    // the entries of std do not depend on the hasher
+    #[cfg(all(feature = "std", feature = "rust_1_57"))]
+    inner: std::collections::hash_map::VacantEntry<'a, K, V>,
+    hasher: PhantomData<&'a S>,
+}
+
+impl<'a, K: Eq + Hash, V, S: BuildHasher> Entry<'a, K, V, S> {
+    /// Ensures a value is in the entry by inserting `default` if empty, and
+    /// returns a mutable reference to the value in the entry.
+    #[inline]
+    pub fn or_try_insert(self, default: V) -> Result<&'a mut V, TryReserveError> {
+        Ok(match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default),
+        })
+    }
+
+    /// Ensures a value is in the entry by inserting the result of `default`
+    /// if empty, and returns a mutable reference to the value in the entry.
+    #[inline]
+    pub fn or_try_insert_with<F: FnOnce() -> V>(
+        self,
+        default: F,
+    ) -> Result<&'a mut V, TryReserveError> {
+        Ok(match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default()),
+        })
+    }
+
+    /// Returns a reference to this entry's key.
+    #[inline]
+    pub fn key(&self) -> &K {
+        match self {
+            Entry::Occupied(entry) => entry.key(),
+            Entry::Vacant(entry) => entry.key(),
+        }
+    }
+
+    /// Provides in-place mutable access to an occupied entry before any
+    /// potential insertion into the map.
+    #[inline]
+    pub fn and_modify<F: FnOnce(&mut V)>(self, f: F) -> Self {
+        match self {
+            Entry::Occupied(mut entry) => {
+                f(entry.get_mut());
+                Entry::Occupied(entry)
+            }
+            Entry::Vacant(entry) => Entry::Vacant(entry),
+        }
+    }
+}
+
+impl<'a, K: Eq + Hash, V: Default, S: BuildHasher> Entry<'a, K, V, S> {
+    /// Ensures a value is in the entry by inserting the default value if
+    /// empty, and returns a mutable reference to the value in the entry.
+    #[inline]
+    pub fn or_default(self) -> Result<&'a mut V, TryReserveError> {
+        self.or_try_insert_with(Default::default)
+    }
+}
+
+impl<'a, K, V, S> OccupiedEntry<'a, K, V, S> {
+    /// Gets a reference to the key in the entry.
+    #[inline(always)]
+    pub fn key(&self) -> &K {
+        self.inner.key()
+    }
+
+    /// Gets a reference to the value in the entry.
+    #[inline(always)]
+    pub fn get(&self) -> &V {
+        self.inner.get()
+    }
+
+    /// Gets a mutable reference to the value in the entry.
+    #[inline(always)]
+    pub fn get_mut(&mut self) -> &mut V {
+        self.inner.get_mut()
+    }
+
+    /// Converts the entry into a mutable reference to its value, with the
+    /// lifetime of the map.
+    #[inline(always)]
+    pub fn into_mut(self) -> &'a mut V {
+        self.inner.into_mut()
+    }
+
+    /// Sets the value of the entry, and returns the old value.
+    #[inline(always)]
+    pub fn insert(&mut self, value: V) -> V {
+        self.inner.insert(value)
+    }
+
+    /// Takes the value out of the map, and returns it.
+    #[inline(always)]
+    pub fn remove(self) -> V {
+        self.inner.remove()
+    }
+
+    /// Takes the key-value pair out of the map, and returns it.
+    #[inline(always)]
+    pub fn remove_entry(self) -> (K, V) {
+        self.inner.remove_entry()
+    }
+}
+
+impl<'a, K: Eq + Hash, V, S: BuildHasher> VacantEntry<'a, K, V, S> {
+    /// Gets a reference to the key that would be used when inserting a
+    /// value through the entry.
+    #[inline(always)]
+    pub fn key(&self) -> &K {
+        self.inner.key()
+    }
+
+    /// Takes ownership of the key.
+    #[inline(always)]
+    pub fn into_key(self) -> K {
+        self.inner.into_key()
+    }
+
+    /// Sets the value of the entry, and returns a mutable reference to it.
+    /// The room for it was reserved by `try_entry`, so this never allocates.
+    #[inline(always)]
+    pub fn insert(self, value: V) -> &'a mut V {
+        self.inner.insert(value)
+    }
+}
+
+impl<K: Debug, V: Debug, S> Debug for Entry<'_, K, V, S> {
+    #[inline]
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Entry::Occupied(entry) => f.debug_tuple("Entry").field(entry).finish(),
+            Entry::Vacant(entry) => f.debug_tuple("Entry").field(entry).finish(),
+        }
+    }
+}
+
+impl<K: Debug, V: Debug, S> Debug for OccupiedEntry<'_, K, V, S> {
+    #[inline]
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("OccupiedEntry")
+            .field("key", self.key())
+            .field("value", self.get())
+            .finish()
+    }
+}
+
+impl<K: Debug, V, S> Debug for VacantEntry<'_, K, V, S> {
+    #[inline]
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_tuple("VacantEntry")
+            .field(self.inner.key())
+            .finish()
+    }
+}
+
+impl<K, V, S> IntoIterator for TryHashMap<K, V, S> {
     type Item = (K, V);
     type IntoIter = IntoIter<K, V>;
 
@@ -114,13 +536,102 @@ impl<K, V> IntoIterator for TryHashMap<K, V> {
     }
 }
 
-impl<K, V> TryClone for TryHashMap<K, V>
+impl<'a, K, V, S> IntoIterator for &'a TryHashMap<K, V, S> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    #[inline(always)]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, K, V, S> IntoIterator for &'a mut TryHashMap<K, V, S> {
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V>;
+
+    #[inline(always)]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<K, V, S> PartialEq for TryHashMap<K, V, S>
+where
+    K: Eq + Hash,
+    V: PartialEq,
+    S: BuildHasher,
+{
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.inner == other.inner
+    }
+}
+
+impl<K, V, S> Eq for TryHashMap<K, V, S>
+where
+    K: Eq + Hash,
+    V: Eq,
+    S: BuildHasher,
+{
+}
+
+impl<K, V, S> TryExtend<(K, V)> for TryHashMap<K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    fn try_extend<I: IntoIterator<Item = (K, V)>>(
+        &mut self,
+        iter: I,
+    ) -> Result<(), TryReserveError> {
+        let iter = iter.into_iter();
+        self.try_reserve(iter.size_hint().0)?;
+        for (k, v) in iter {
+            self.insert(k, v)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a, K, V, S> TryExtend<(&'a K, &'a V)> for TryHashMap<K, V, S>
+where
+    K: Eq + Hash + Copy + 'a,
+    V: Copy + 'a,
+    S: BuildHasher,
+{
+    #[inline]
+    fn try_extend<I>(&mut self, iter: I) -> Result<(), TryReserveError>
+    where
+        I: IntoIterator<Item = (&'a K, &'a V)>,
+    {
+        self.try_extend(iter.into_iter().map(|(&k, &v)| (k, v)))
+    }
+}
+
+impl<K, V, S> TryFromIterator<(K, V)> for TryHashMap<K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher + Default,
+{
+    fn try_from_iterator<T: IntoIterator<Item = (K, V)>>(
+        iterator: T,
+    ) -> Result<Self, TryReserveError> {
+        let mut map = Self::try_with_hasher(S::default())?;
+        map.try_extend(iterator)?;
+        Ok(map)
+    }
+}
+
+impl<K, V, S> TryClone for TryHashMap<K, V, S>
 where
     K: Eq + Hash + TryClone,
     V: TryClone,
+    S: BuildHasher + Clone,
 {
     fn try_clone(&self) -> Result<Self, TryReserveError> {
-        let mut clone = Self::with_capacity(self.inner.len())?;
+        let mut clone =
+            Self::try_with_capacity_and_hasher(self.inner.len(), self.inner.hasher().clone())?;
 
         for (key, value) in self.inner.iter() {
             clone.insert(key.try_clone()?, value.try_clone()?)?;
@@ -130,10 +641,580 @@ where
     }
 }
 
+/// TryHashSet is a hash set built on `TryHashMap`, whose insertions return
+/// an error instead of aborting when the allocation fails.
+pub struct TryHashSet<T, S = DefaultHashBuilder> {
+    map: TryHashMap<T, (), S>,
+}
+
+impl<T, S: Default> Default for TryHashSet<T, S> {
+    #[inline(always)]
+    fn default() -> Self {
+        Self {
+            map: Default::default(),
+        }
+    }
+}
+
+impl<T: Debug, S> Debug for TryHashSet<T, S> {
+    #[inline]
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+impl<T, S> TryHashSet<T, S> {
+    #[inline(always)]
+    pub fn iter(&self) -> Keys<'_, T, ()> {
+        self.map.keys()
+    }
+
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    #[inline(always)]
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns the number of elements the set can hold without allocating.
+    #[inline(always)]
+    pub fn capacity(&self) -> usize {
+        self.map.capacity()
+    }
+
+    /// Removes all the elements, keeping the allocated memory for reuse.
+    #[inline(always)]
+    pub fn clear(&mut self) {
+        self.map.clear()
+    }
+
+    /// Keeps only the elements for which `f` returns true.
+    #[inline]
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&T) -> bool,
+    {
+        self.map.retain(|k, _| f(k))
+    }
+}
+
+impl<T> TryHashSet<T>
+where
+    T: Eq + Hash,
+{
+    /// Creates an empty set with the default hasher, this does not allocate.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[inline]
+    pub fn with_capacity(capacity: usize) -> Result<Self, TryReserveError> {
+        Ok(Self {
+            map: TryHashMap::with_capacity(capacity)?,
+        })
+    }
+}
+
+impl<T, S> TryHashSet<T, S>
+where
+    T: Eq + Hash,
+    S: BuildHasher,
+{
+    /// Creates an empty set which will use `hash_builder` to hash the
+    /// elements. It does not allocate.
+    #[inline]
+    pub fn try_with_hasher(hash_builder: S) -> Result<Self, TryReserveError> {
+        Self::try_with_capacity_and_hasher(0, hash_builder)
+    }
+
+    /// Creates a set that can hold `capacity` elements without allocating,
+    /// using `hash_builder` to hash them.
+    #[inline]
+    pub fn try_with_capacity_and_hasher(
+        capacity: usize,
+        hash_builder: S,
+    ) -> Result<Self, TryReserveError> {
+        Ok(Self {
+            map: TryHashMap::try_with_capacity_and_hasher(capacity, hash_builder)?,
+        })
+    }
+
+    /// Adds a value to the set, returns whether it was not present yet.
+    #[inline]
+    pub fn try_insert(&mut self, value: T) -> Result<bool, TryReserveError> {
+        if self.map.contains_key(&value) {
+            return Ok(false);
+        }
+        Ok(self.map.insert(value, ())?.is_none())
+    }
+
+    #[inline(always)]
+    pub fn contains<Q>(&self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.map.contains_key(value)
+    }
+
+    #[inline(always)]
+    pub fn get<Q>(&self, value: &Q) -> Option<&T>
+    where
+        T: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.map.get_key_value(value).map(|(k, _)| k)
+    }
+
+    /// Removes a value from the set, returns whether it was present.
+    #[inline(always)]
+    pub fn remove<Q>(&mut self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.map.remove(value).is_some()
+    }
+
+    /// Removes a value from the set and returns it, if it was present.
+    #[inline(always)]
+    pub fn take<Q>(&mut self, value: &Q) -> Option<T>
+    where
+        T: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.map.remove_entry(value).map(|(k, _)| k)
+    }
+
+    /// Reserves room for at least `additional` more elements, so that as
+    /// many insertions of new values will not allocate.
+    #[inline(always)]
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        self.map.try_reserve(additional)
+    }
+
+    /// Keeps only the values of `self` that are also in `other`. Removing
+    /// values never allocates, so this cannot fail.
+    #[inline]
+    pub fn intersection_with(&mut self, other: &Self) {
+        self.retain(|value| other.contains(value))
+    }
+
+    /// Removes every value of `other` from `self`. Removing values never
+    /// allocates, so this cannot fail.
+    #[inline]
+    pub fn difference_with(&mut self, other: &Self) {
+        if self.len() <= other.len() {
+            self.retain(|value| !other.contains(value))
+        } else {
+            for value in other.iter() {
+                self.remove(value);
+            }
+        }
+    }
+}
+
+impl<T, S> TryHashSet<T, S>
+where
+    T: Eq + Hash + TryClone,
+    S: BuildHasher + Clone,
+{
+    /// Returns a new set holding a clone of every value in `self` or
+    /// `other`, returning an error if an allocation fails.
+    pub fn try_union(&self, other: &Self) -> Result<Self, TryReserveError> {
+        let mut union = self.try_clone()?;
+        union.try_union_with(other)?;
+        Ok(union)
+    }
+
+    /// Returns a new set holding a clone of every value in both `self` and
+    /// `other`, returning an error if an allocation fails.
+    pub fn try_intersection(&self, other: &Self) -> Result<Self, TryReserveError> {
+        let (small, large) = if self.len() <= other.len() {
+            (self, other)
+        } else {
+            (other, self)
+        };
+        let mut intersection =
+            Self::try_with_capacity_and_hasher(small.len(), self.map.hasher().clone())?;
+        for value in small.iter().filter(|value| large.contains(*value)) {
+            intersection.try_insert(value.try_clone()?)?;
+        }
+        Ok(intersection)
+    }
+
+    /// Returns a new set holding a clone of every value in `self` but not
+    /// in `other`, returning an error if an allocation fails.
+    pub fn try_difference(&self, other: &Self) -> Result<Self, TryReserveError> {
+        let mut difference =
+            Self::try_with_capacity_and_hasher(self.len(), self.map.hasher().clone())?;
+        for value in self.iter().filter(|value| !other.contains(*value)) {
+            difference.try_insert(value.try_clone()?)?;
+        }
+        Ok(difference)
+    }
+
+    /// Adds a clone of every value of `other` to `self`.
+    ///
+    /// The room for the missing values is reserved first, so only the
+    /// cloning of a value can fail afterwards, in which case the values
+    /// added so far are kept.
+    pub fn try_union_with(&mut self, other: &Self) -> Result<(), TryReserveError> {
+        let missing = other.iter().filter(|value| !self.contains(*value)).count();
+        self.try_reserve(missing)?;
+        for value in other.iter() {
+            if !self.contains(value) {
+                self.try_insert(value.try_clone()?)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'a, T, S> IntoIterator for &'a TryHashSet<T, S> {
+    type Item = &'a T;
+    type IntoIter = Keys<'a, T, ()>;
+
+    #[inline(always)]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T, S> PartialEq for TryHashSet<T, S>
+where
+    T: Eq + Hash,
+    S: BuildHasher,
+{
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.map == other.map
+    }
+}
+
+impl<T, S> Eq for TryHashSet<T, S>
+where
+    T: Eq + Hash,
+    S: BuildHasher,
+{
+}
+
+impl<T, S> TryExtend<T> for TryHashSet<T, S>
+where
+    T: Eq + Hash,
+    S: BuildHasher,
+{
+    fn try_extend<I: IntoIterator<Item = T>>(&mut self, iter: I) -> Result<(), TryReserveError> {
+        let iter = iter.into_iter();
+        self.try_reserve(iter.size_hint().0)?;
+        for value in iter {
+            self.try_insert(value)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a, T, S> TryExtend<&'a T> for TryHashSet<T, S>
+where
+    T: Eq + Hash + Copy + 'a,
+    S: BuildHasher,
+{
+    #[inline]
+    fn try_extend<I: IntoIterator<Item = &'a T>>(
+        &mut self,
+        iter: I,
+    ) -> Result<(), TryReserveError> {
+        self.try_extend(iter.into_iter().copied())
+    }
+}
+
+impl<T, S> TryFromIterator<T> for TryHashSet<T, S>
+where
+    T: Eq + Hash,
+    S: BuildHasher + Default,
+{
+    fn try_from_iterator<I: IntoIterator<Item = T>>(iterator: I) -> Result<Self, TryReserveError> {
+        let mut set = Self::try_with_hasher(S::default())?;
+        set.try_extend(iterator)?;
+        Ok(set)
+    }
+}
+
+impl<T, S> TryClone for TryHashSet<T, S>
+where
+    T: Eq + Hash + TryClone,
+    S: BuildHasher + Clone,
+{
+    #[inline]
+    fn try_clone(&self) -> Result<Self, TryReserveError> {
+        Ok(Self {
+            map: self.map.try_clone()?,
+        })
+    }
+}
+
 #[test]
 fn tryhashmap_oom() {
-    match TryHashMap::<char, char>::default().reserve(core::usize::MAX) {
-        Ok(_) => panic!("it should be OOM"),
-        _ => (),
+    assert!(
+        TryHashMap::<char, char>::default()
+            .try_reserve(usize::MAX)
+            .is_err(),
+        "it should be OOM"
+    );
+}
+
+#[test]
+fn tryhashmap_insert_get_remove() {
+    let mut map = TryHashMap::with_capacity(1).unwrap();
+    for i in 0..100 {
+        assert_eq!(map.insert(i, i + 1).unwrap(), None);
     }
+    assert_eq!(map.insert(3, 0).unwrap(), Some(4));
+    assert_eq!(map.len(), 100);
+    assert_eq!(map.get(&50), Some(&51));
+    assert_eq!(map.remove(&50), Some(51));
+    assert_eq!(map.get(&50), None);
+    assert_eq!(map.iter().count(), 99);
+}
+
+#[test]
+fn tryhashmap_try_clone() {
+    let mut map = TryHashMap::new();
+    map.insert(1u8, 2u8).unwrap();
+    let clone = map.try_clone().unwrap();
+    assert_eq!(clone.get(&1), Some(&2));
+    assert_eq!(clone.len(), 1);
+}
+
+#[test]
+fn tryhashmap_capacity_overflow() {
+    let mut map = TryHashMap::<u64, u64>::default();
+    map.insert(1, 1).unwrap();
+    assert!(
+        map.try_reserve(usize::MAX).is_err(),
+        "capacity calculation should overflow"
+    );
+    assert_eq!(map.get(&1), Some(&1));
+}
+
+#[test]
+fn tryhashmap_entry() {
+    let mut map = TryHashMap::new();
+    *map.try_entry("a").unwrap().or_try_insert(1).unwrap() += 10;
+    *map.try_entry("a").unwrap().or_try_insert(1).unwrap() += 10;
+    *map.try_entry("b").unwrap().or_default().unwrap() += 1;
+    map.try_entry("c")
+        .unwrap()
+        .and_modify(|v| *v += 1)
+        .or_try_insert_with(|| 5)
+        .unwrap();
+    map.try_entry("c")
+        .unwrap()
+        .and_modify(|v| *v += 1)
+        .or_try_insert_with(|| 5)
+        .unwrap();
+    assert_eq!(map.get("a"), Some(&21));
+    assert_eq!(map.get("b"), Some(&1));
+    assert_eq!(map.get("c"), Some(&6));
+
+    match map.try_entry("a").unwrap() {
+        Entry::Occupied(entry) => assert_eq!(entry.remove_entry(), ("a", 21)),
+        Entry::Vacant(_) => panic!("a should be occupied"),
+    }
+    match map.try_entry("a").unwrap() {
+        Entry::Occupied(_) => panic!("a should be vacant"),
+        Entry::Vacant(entry) => assert_eq!(entry.into_key(), "a"),
+    }
+    assert_eq!(map.len(), 2);
+}
+
+#[test]
+fn tryhashmap_std_parity() {
+    let mut map = TryHashMap::new();
+    for i in 0..10 {
+        map.insert(i, i).unwrap();
+    }
+    assert!(map.contains_key(&3));
+    assert_eq!(map.get_key_value(&3), Some((&3, &3)));
+    *map.get_mut(&3).unwrap() = 30;
+    assert_eq!(map.remove_entry(&3), Some((3, 30)));
+    assert!(!map.contains_key(&3));
+
+    for (_, v) in &mut map {
+        *v += 1;
+    }
+    map.values_mut().for_each(|v| *v *= 2);
+    map.iter_mut().for_each(|(_, v)| *v -= 2);
+    assert!((&map).into_iter().all(|(k, v)| *v == 2 * k));
+    assert_eq!(map.keys().sum::<i32>(), 42);
+    assert_eq!(map.values().sum::<i32>(), 84);
+
+    map.retain(|k, _| k % 2 == 0);
+    assert_eq!(map.len(), 5);
+    let clone = map.try_clone().unwrap();
+    assert_eq!(clone, map);
+
+    let mut drained: std::vec::Vec<_> = map.drain().collect();
+    drained.sort_unstable();
+    assert_eq!(drained, [(0, 0), (2, 4), (4, 8), (6, 12), (8, 16)]);
+    assert!(map.is_empty());
+    assert_ne!(clone, map);
+
+    let mut map = clone;
+    map.clear();
+    assert!(map.is_empty());
+}
+
+#[test]
+fn tryhashmap_capacity() {
+    let mut map =
+        TryHashMap::try_with_capacity_and_hasher(100, DefaultHashBuilder::default()).unwrap();
+    let capacity = map.capacity();
+    assert!(capacity >= 100);
+    for i in 0..capacity {
+        assert_eq!(map.try_insert_within_capacity(i, i), Ok(None));
+    }
+    assert_eq!(map.capacity(), capacity);
+    assert_eq!(map.try_insert_within_capacity(0, 1), Ok(Some(0)));
+    assert_eq!(
+        map.try_insert_within_capacity(capacity, 0),
+        Err((capacity, 0))
+    );
+
+    let mut map = TryHashMap::with_capacity(1000).unwrap();
+    for i in 0..10 {
+        map.insert(i, i).unwrap();
+    }
+    map.try_shrink_to(50).unwrap();
+    assert!(map.capacity() >= 50 && map.capacity() < 1000);
+    map.shrink_to_fit();
+    assert!(map.capacity() >= 10 && map.capacity() < 50);
+    assert_eq!(map.len(), 10);
+    assert_eq!(map.get(&9), Some(&9));
+
+    map.try_reserve(1000).unwrap();
+    assert!(map.capacity() >= 1010);
+
+    // shrinking is best effort, a failed allocation leaves the map as it was
+    let capacity = map.capacity();
+    crate::test_alloc::with_alloc_budget(0, || map.shrink_to_fit());
+    assert_eq!(map.capacity(), capacity);
+    assert!(crate::test_alloc::with_alloc_budget(0, || map.try_shrink_to(0)).is_err());
+    assert_eq!(map.capacity(), capacity);
+    assert_eq!(map.len(), 10);
+    assert!((0..10).all(|i| map.get(&i) == Some(&i)));
+}
+
+#[test]
+fn tryhashmap_custom_hasher() {
+    use core::hash::{BuildHasherDefault, Hasher};
+
+    #[derive(Default)]
+    struct Fnv(u64);
+
+    impl Hasher for Fnv {
+        fn finish(&self) -> u64 {
+            self.0
+        }
+
+        fn write(&mut self, bytes: &[u8]) {
+            for byte in bytes {
+                self.0 = (self.0 ^ u64::from(*byte)).wrapping_mul(0x100000001b3);
+            }
+        }
+    }
+
+    type FnvBuilder = BuildHasherDefault<Fnv>;
+
+    let mut map = TryHashMap::try_with_hasher(FnvBuilder::default()).unwrap();
+    assert_eq!(map.capacity(), 0);
+    for i in 0..100u32 {
+        map.insert(i, i).unwrap();
+    }
+    *map.try_entry(7).unwrap().or_default().unwrap() += 1;
+    assert_eq!(map.get(&7), Some(&8));
+
+    let clone = map.try_clone().unwrap();
+    assert_eq!(clone, map);
+
+    let map: TryHashMap<u32, u32, FnvBuilder> =
+        TryHashMap::try_with_capacity_and_hasher(10, FnvBuilder::default()).unwrap();
+    assert!(map.capacity() >= 10);
+
+    let map: TryHashMap<u32, u32, FnvBuilder> = Default::default();
+    assert!(map.is_empty());
+    let set: TryHashSet<u32, FnvBuilder> = Default::default();
+    assert!(set.is_empty());
+}
+
+#[test]
+fn tryhashset() {
+    let mut set = TryHashSet::with_capacity(4).unwrap();
+    assert!(set.try_insert(1).unwrap());
+    assert!(!set.try_insert(1).unwrap());
+    assert!(set.try_insert(2).unwrap());
+    assert!(set.contains(&1));
+    assert_eq!(set.get(&2), Some(&2));
+    assert!(set.remove(&1));
+    assert!(!set.remove(&1));
+    assert_eq!(set.take(&2), Some(2));
+    assert!(set.is_empty());
+
+    set.try_reserve(100).unwrap();
+    assert!(set.capacity() >= 100);
+    for i in 0..100 {
+        set.try_insert(i).unwrap();
+    }
+    let clone = set.try_clone().unwrap();
+    assert_eq!(clone, set);
+    assert_eq!((&clone).into_iter().count(), 100);
+}
+
+#[test]
+fn tryhashset_algebra() {
+    let mut a = TryHashSet::new();
+    let mut b = TryHashSet::new();
+    for i in 0..30u32 {
+        if i % 2 == 0 {
+            a.try_insert(i).unwrap();
+        }
+        if i % 3 == 0 {
+            b.try_insert(i).unwrap();
+        }
+    }
+
+    let union = a.try_union(&b).unwrap();
+    assert_eq!(union.len(), 20);
+    assert!((0..30).all(|i| union.contains(&i) == (i % 2 == 0 || i % 3 == 0)));
+    let intersection = a.try_intersection(&b).unwrap();
+    assert!((0..30).all(|i| intersection.contains(&i) == (i % 6 == 0)));
+    let difference = a.try_difference(&b).unwrap();
+    assert!((0..30).all(|i| difference.contains(&i) == (i % 2 == 0 && i % 3 != 0)));
+
+    let mut c = a.try_clone().unwrap();
+    c.try_union_with(&b).unwrap();
+    assert_eq!(c, union);
+    let mut c = a.try_clone().unwrap();
+    c.intersection_with(&b);
+    assert_eq!(c, intersection);
+    let mut c = a.try_clone().unwrap();
+    c.difference_with(&b);
+    assert_eq!(c, difference);
+}
+
+#[test]
+fn try_collect_hash() {
+    use crate::TryCollect;
+
+    let map: TryHashMap<_, _> = (0..10).map(|i| (i, i * 2)).try_collect().unwrap();
+    assert_eq!(map.len(), 10);
+    assert!(map.capacity() >= 10);
+    assert_eq!(map.get(&4), Some(&8));
+
+    let set: TryHashSet<_> = vec![1, 2, 2, 3].try_collect().unwrap();
+    assert_eq!(set.len(), 3);
 }
@@ -22,16 +22,12 @@
 //! can't return a Result to indicate allocation failure.
 
 #![cfg_attr(not(test), no_std)]
-#![cfg_attr(all(feature = "unstable", not(feature = "rust_1_57")), feature(try_reserve))]
+#![cfg_attr(
+    all(feature = "unstable", not(feature = "rust_1_57")),
+    feature(try_reserve_kind)
+)]
 #![cfg_attr(feature = "unstable", feature(min_specialization))]
 #![cfg_attr(feature = "unstable", feature(allocator_api))]
-#![cfg_attr(feature = "unstable", feature(dropck_eyepatch))]
-#![cfg_attr(feature = "unstable", feature(ptr_internals))]
-#![cfg_attr(feature = "unstable", feature(core_intrinsics))]
-#![cfg_attr(all(feature = "unstable", not(feature = "rust_1_57")), feature(maybe_uninit_ref))]
-#![cfg_attr(feature = "unstable", feature(maybe_uninit_slice))]
-#![cfg_attr(feature = "unstable", feature(maybe_uninit_extra))]
-#![cfg_attr(feature = "unstable", feature(maybe_uninit_uninit_array))]
 extern crate alloc;
 #[cfg(feature = "std")]
 extern crate std;
@@ -45,14 +41,17 @@ pub mod rc;
 pub use rc::*;
 pub mod arc;
 pub use arc::*;
-#[cfg(feature = "unstable")]
 pub mod btree;
-#[cfg(not(feature = "unstable"))]
 pub mod hashmap;
-#[cfg(not(feature = "unstable"))]
 pub use hashmap::*;
+pub mod slice;
+pub use slice::*;
+pub mod string;
+pub use string::*;
 #[macro_use]
 pub mod format;
+#[cfg(test)]
+mod test_alloc;
 pub mod try_clone;
 
 #[cfg(all(feature = "unstable", not(feature = "rust_1_57")))]
@@ -1,9 +1,21 @@
 //! Implement a Fallible Rc
 use super::FallibleBox;
+use super::TryClone;
+use crate::boxed::alloc;
 use crate::TryReserveError;
+use alloc::alloc::{dealloc, Layout};
 use alloc::boxed::Box;
 use alloc::rc::Rc;
+use core::cell::Cell;
+use core::marker::PhantomData;
+use core::ops::Deref;
+use core::ptr::NonNull;
+
 /// trait to implement Fallible Rc
+#[deprecated(
+    since = "0.4.7",
+    note = "⚠️️️this function is not completely fallible, Rc::from(Box) reallocates to add the reference counts. use TryRc instead"
+)]
 pub trait FallibleRc<T> {
     /// try creating a new Rc, returning a Result<Box<T>,
     /// TryReserveError> if allocation failed
@@ -12,6 +24,7 @@ pub trait FallibleRc<T> {
         Self: Sized;
 }
 
+#[allow(deprecated)]
 impl<T> FallibleRc<T> for Rc<T> {
     fn try_new(t: T) -> Result<Self, TryReserveError> {
         let b = <Box<T> as FallibleBox<T>>::try_new(t)?;
@@ -19,8 +32,254 @@ impl<T> FallibleRc<T> for Rc<T> {
     }
 }
 
+struct RcBox<T> {
+    strong: Cell<usize>,
+    // all the strong pointers together hold one implicit weak reference,
+    // see std::rc::Rc
+    weak: Cell<usize>,
+    value: T,
+}
+
+/// TryRc is a single-threaded reference-counting pointer whose reference
+/// counts and value live in a single block, allocated fallibly by
+/// `try_new`.
+///
+/// Cloning a `TryRc` or downgrading it to a `TryWeak` only updates the
+/// reference counts, so it never allocates.
+pub struct TryRc<T> {
+    ptr: NonNull<RcBox<T>>,
+    phantom: PhantomData<RcBox<T>>,
+}
+
+/// TryWeak is a non-owning reference to the value of a `TryRc`, see
+/// std::rc::Weak
+pub struct TryWeak<T> {
+    ptr: NonNull<RcBox<T>>,
+}
+
+impl<T> TryRc<T> {
+    /// try creating a new TryRc, returning an error if the allocation of
+    /// the reference counts and the value failed
+    pub fn try_new(value: T) -> Result<Self, TryReserveError> {
+        let layout = Layout::new::<RcBox<T>>();
+        let ptr = alloc(layout)?.cast::<RcBox<T>>();
+        unsafe {
+            core::ptr::write(
+                ptr.as_ptr(),
+                RcBox {
+                    strong: Cell::new(1),
+                    weak: Cell::new(1),
+                    value,
+                },
+            );
+        }
+        Ok(Self {
+            ptr,
+            phantom: PhantomData,
+        })
+    }
+
+    /// Returns the inner value if `this` is the only strong reference to
+    /// it, otherwise gives `this` back. Outstanding `TryWeak` will fail to
+    /// upgrade afterwards.
+    pub fn try_unwrap(this: Self) -> Result<T, Self> {
+        if Self::strong_count(&this) != 1 {
+            return Err(this);
+        }
+
+        unsafe {
+            let value = core::ptr::read(&this.inner().value);
+            this.inner().strong.set(0);
+            // release the implicit weak reference held by the strong pointers
+            drop(TryWeak { ptr: this.ptr });
+            core::mem::forget(this);
+            Ok(value)
+        }
+    }
+
+    /// Returns a mutable reference to the inner value if there are no
+    /// other `TryRc` or `TryWeak` pointing to it.
+    #[inline]
+    pub fn get_mut(this: &mut Self) -> Option<&mut T> {
+        if Self::strong_count(this) == 1 && Self::weak_count(this) == 0 {
+            unsafe { Some(&mut (*this.ptr.as_ptr()).value) }
+        } else {
+            None
+        }
+    }
+
+    /// Creates a new `TryWeak` pointer to this allocation.
+    #[inline]
+    pub fn downgrade(this: &Self) -> TryWeak<T> {
+        let weak = &this.inner().weak;
+        weak.set(
+            weak.get()
+                .checked_add(1)
+                .expect("TryRc reference count overflow"),
+        );
+        TryWeak { ptr: this.ptr }
+    }
+
+    /// Gets the number of `TryRc` pointing to this allocation.
+    #[inline]
+    pub fn strong_count(this: &Self) -> usize {
+        this.inner().strong.get()
+    }
+
+    /// Gets the number of `TryWeak` pointing to this allocation.
+    #[inline]
+    pub fn weak_count(this: &Self) -> usize {
+        this.inner().weak.get() - 1
+    }
+
+    /// Returns true if the two `TryRc` point to the same allocation.
+    #[inline]
+    pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+        this.ptr == other.ptr
+    }
+
+    #[inline(always)]
+    fn inner(&self) -> &RcBox<T> {
+        unsafe { self.ptr.as_ref() }
+    }
+}
+
+impl<T> Clone for TryRc<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        let strong = &self.inner().strong;
+        strong.set(
+            strong
+                .get()
+                .checked_add(1)
+                .expect("TryRc reference count overflow"),
+        );
+        Self {
+            ptr: self.ptr,
+            phantom: PhantomData,
+        }
+    }
+}
+
+/// Just a TryClone boilerplate for TryRc
+impl<T> TryClone for TryRc<T> {
+    #[inline(always)]
+    fn try_clone(&self) -> Result<Self, TryReserveError> {
+        Ok(self.clone())
+    }
+}
+
+impl<T> Drop for TryRc<T> {
+    fn drop(&mut self) {
+        let strong = &self.inner().strong;
+        strong.set(strong.get() - 1);
+        if strong.get() == 0 {
+            unsafe {
+                core::ptr::drop_in_place(&mut (*self.ptr.as_ptr()).value);
+            }
+            // release the implicit weak reference held by the strong pointers
+            drop(TryWeak { ptr: self.ptr });
+        }
+    }
+}
+
+impl<T> Deref for TryRc<T> {
+    type Target = T;
+
+    #[inline(always)]
+    fn deref(&self) -> &T {
+        &self.inner().value
+    }
+}
+
+impl<T: core::fmt::Debug> core::fmt::Debug for TryRc<T> {
+    #[inline]
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+impl<T> TryWeak<T> {
+    /// Attempts to get a `TryRc` to the value, returns None if it has
+    /// already been dropped.
+    #[inline]
+    pub fn upgrade(&self) -> Option<TryRc<T>> {
+        let strong = self.strong();
+        if strong.get() == 0 {
+            return None;
+        }
+        strong.set(
+            strong
+                .get()
+                .checked_add(1)
+                .expect("TryRc reference count overflow"),
+        );
+        Some(TryRc {
+            ptr: self.ptr,
+            phantom: PhantomData,
+        })
+    }
+
+    /// Gets the number of `TryRc` pointing to this allocation.
+    #[inline]
+    pub fn strong_count(&self) -> usize {
+        self.strong().get()
+    }
+
+    /// Returns true if the two `TryWeak` point to the same allocation.
+    #[inline]
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        self.ptr == other.ptr
+    }
+
+    // the value may already be dropped, so never borrow the whole RcBox
+    #[inline(always)]
+    fn strong(&self) -> &Cell<usize> {
+        unsafe { &(*self.ptr.as_ptr()).strong }
+    }
+
+    #[inline(always)]
+    fn weak(&self) -> &Cell<usize> {
+        unsafe { &(*self.ptr.as_ptr()).weak }
+    }
+}
+
+impl<T> Clone for TryWeak<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        let weak = self.weak();
+        weak.set(
+            weak.get()
+                .checked_add(1)
+                .expect("TryRc reference count overflow"),
+        );
+        Self { ptr: self.ptr }
+    }
+}
+
+impl<T> Drop for TryWeak<T> {
+    fn drop(&mut self) {
+        let weak = self.weak();
+        weak.set(weak.get() - 1);
+        if weak.get() == 0 {
+            unsafe {
+                dealloc(self.ptr.as_ptr().cast(), Layout::new::<RcBox<T>>());
+            }
+        }
+    }
+}
+
+impl<T> core::fmt::Debug for TryWeak<T> {
+    #[inline]
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("(TryWeak)")
+    }
+}
+
 #[cfg(test)]
 mod test {
+    use super::*;
+
     #[test]
     fn fallible_rc() {
         use std::rc::Rc;
@@ -32,4 +291,47 @@ mod test {
         let _y = Rc::clone(&x);
         assert!(Rc::get_mut(&mut x).is_none());
     }
+
+    #[test]
+    fn tryrc() {
+        let mut x = TryRc::try_new(3).unwrap();
+        *TryRc::get_mut(&mut x).unwrap() = 4;
+        assert_eq!(*x, 4);
+
+        let y = x.try_clone().unwrap();
+        assert_eq!(TryRc::strong_count(&x), 2);
+        assert!(TryRc::ptr_eq(&x, &y));
+        assert!(TryRc::get_mut(&mut x).is_none());
+
+        let x = TryRc::try_unwrap(x).unwrap_err();
+        drop(y);
+        assert_eq!(TryRc::try_unwrap(x).unwrap(), 4);
+    }
+
+    #[test]
+    fn tryrc_weak() {
+        let mut x = TryRc::try_new(3).unwrap();
+        let weak = TryRc::downgrade(&x);
+        assert_eq!(TryRc::weak_count(&x), 1);
+        assert!(TryRc::get_mut(&mut x).is_none());
+
+        let y = weak.upgrade().unwrap();
+        assert_eq!(*y, 3);
+        assert_eq!(weak.strong_count(), 2);
+        drop(y);
+
+        assert_eq!(TryRc::try_unwrap(x).unwrap(), 3);
+        assert!(weak.upgrade().is_none());
+        assert_eq!(weak.strong_count(), 0);
+    }
+
+    #[test]
+    fn tryrc_drop() {
+        let value = Rc::new(());
+        let x = TryRc::try_new(value.clone()).unwrap();
+        let weak = TryRc::downgrade(&x);
+        drop(x);
+        assert_eq!(Rc::strong_count(&value), 1);
+        assert!(weak.clone().upgrade().is_none());
+    }
 }
@@ -0,0 +1,307 @@
+//! Implement fallible stable sorting on slices
+use super::FallibleVec;
+use crate::TryReserveError;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::mem;
+use core::ptr;
+
+/// slices shorter than this are insertion sorted, without any buffer
+const MAX_INSERTION: usize = 20;
+
+/// trait implementing the stable sorts of `[T]` with fallible
+/// allocation of their scratch buffer. On error the slice is left in
+/// its original order.
+/// # Examples
+///
+/// ```
+/// use fallible_collections::FallibleSlice;
+///
+/// let mut v = [5, 4, 1, 3, 2];
+/// v.try_sort().unwrap();
+/// assert_eq!(v, [1, 2, 3, 4, 5]);
+/// ```
+pub trait FallibleSlice<T> {
+    /// Fallible version of `[T]::sort`
+    fn try_sort(&mut self) -> Result<(), TryReserveError>
+    where
+        T: Ord;
+    /// Fallible version of `[T]::sort_by`
+    fn try_sort_by<F>(&mut self, compare: F) -> Result<(), TryReserveError>
+    where
+        F: FnMut(&T, &T) -> Ordering;
+    /// Fallible version of `[T]::sort_by_key`
+    fn try_sort_by_key<K, F>(&mut self, f: F) -> Result<(), TryReserveError>
+    where
+        F: FnMut(&T) -> K,
+        K: Ord;
+    /// Fallible version of `[T]::sort_by_cached_key`, the keys buffer
+    /// is allocated before `f` is called
+    fn try_sort_by_cached_key<K, F>(&mut self, f: F) -> Result<(), TryReserveError>
+    where
+        F: FnMut(&T) -> K,
+        K: Ord;
+}
+
+impl<T> FallibleSlice<T> for [T] {
+    #[inline]
+    fn try_sort(&mut self) -> Result<(), TryReserveError>
+    where
+        T: Ord,
+    {
+        merge_sort(self, &mut T::lt)
+    }
+
+    #[inline]
+    fn try_sort_by<F>(&mut self, mut compare: F) -> Result<(), TryReserveError>
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        merge_sort(self, &mut |a, b| compare(a, b) == Ordering::Less)
+    }
+
+    #[inline]
+    fn try_sort_by_key<K, F>(&mut self, mut f: F) -> Result<(), TryReserveError>
+    where
+        F: FnMut(&T) -> K,
+        K: Ord,
+    {
+        merge_sort(self, &mut |a, b| f(a).lt(&f(b)))
+    }
+
+    fn try_sort_by_cached_key<K, F>(&mut self, mut f: F) -> Result<(), TryReserveError>
+    where
+        F: FnMut(&T) -> K,
+        K: Ord,
+    {
+        let len = self.len();
+        if len < 2 {
+            return Ok(());
+        }
+        let mut indices: Vec<(K, usize)> = FallibleVec::try_with_capacity(len)?;
+        indices.extend(self.iter().map(&mut f).enumerate().map(|(i, k)| (k, i)));
+        // the indices make every key unique, so an unstable sort is stable
+        indices.sort_unstable();
+        // apply the permutation in place, an index smaller than i has
+        // already been swapped away, so follow it to its new position
+        for i in 0..len {
+            let mut index = indices[i].1;
+            while index < i {
+                index = indices[index].1;
+            }
+            indices[i].1 = index;
+            self.swap(i, index);
+        }
+        Ok(())
+    }
+}
+
+/// stable merge sort, the scratch buffer is allocated before the slice
+/// is touched
+fn merge_sort<T, F>(v: &mut [T], is_less: &mut F) -> Result<(), TryReserveError>
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    // sorting zero sized types has no meaningful behavior
+    if mem::size_of::<T>() == 0 {
+        return Ok(());
+    }
+    let len = v.len();
+    if len <= MAX_INSERTION {
+        insertion_sort(v, is_less);
+        return Ok(());
+    }
+    // merging never needs to copy more than the shorter run
+    let mut buf: Vec<T> = FallibleVec::try_with_capacity(len / 2)?;
+    // buf len stays 0, so elements in it are never dropped twice
+    unsafe { merge_sort_rec(v, buf.as_mut_ptr(), is_less) };
+    Ok(())
+}
+
+/// # Safety
+/// `buf` must be valid for writes of `v.len() / 2` elements
+unsafe fn merge_sort_rec<T, F>(v: &mut [T], buf: *mut T, is_less: &mut F)
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    let len = v.len();
+    if len <= MAX_INSERTION {
+        insertion_sort(v, is_less);
+        return;
+    }
+    let mid = len / 2;
+    merge_sort_rec(&mut v[..mid], buf, is_less);
+    merge_sort_rec(&mut v[mid..], buf, is_less);
+    // runs already in order
+    if !is_less(&v[mid], &v[mid - 1]) {
+        return;
+    }
+    merge(v, mid, buf, is_less);
+}
+
+fn insertion_sort<T, F>(v: &mut [T], is_less: &mut F)
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    for i in 1..v.len() {
+        let mut j = i;
+        while j > 0 && is_less(&v[j], &v[j - 1]) {
+            v.swap(j, j - 1);
+            j -= 1;
+        }
+    }
+}
+
+/// Merges the sorted runs `v[..mid]` and `v[mid..]`, copying the shorter
+/// one into `buf`.
+///
+/// # Safety
+/// `buf` must be valid for writes of `min(mid, v.len() - mid)` elements
+unsafe fn merge<T, F>(v: &mut [T], mid: usize, buf: *mut T, is_less: &mut F)
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    let len = v.len();
+    let v = v.as_mut_ptr();
+    let v_mid = v.add(mid);
+    let v_end = v.add(len);
+
+    // if is_less panics, dropping the hole copies back the part of the
+    // run still in buf, so every element ends up in v exactly once
+    let mut hole;
+    if mid <= len - mid {
+        // left run is shorter, merge forwards
+        ptr::copy_nonoverlapping(v, buf, mid);
+        hole = MergeHole {
+            start: buf,
+            end: buf.add(mid),
+            dest: v,
+        };
+        let mut right = v_mid;
+        while hole.start < hole.end && right < v_end {
+            // take from the left run on ties to stay stable
+            let to_copy = if is_less(&*right, &*hole.start) {
+                let src = right;
+                right = right.add(1);
+                src
+            } else {
+                let src = hole.start;
+                hole.start = hole.start.add(1);
+                src
+            };
+            ptr::copy_nonoverlapping(to_copy, hole.dest, 1);
+            hole.dest = hole.dest.add(1);
+        }
+    } else {
+        // right run is shorter, merge backwards
+        ptr::copy_nonoverlapping(v_mid, buf, len - mid);
+        hole = MergeHole {
+            start: buf,
+            end: buf.add(len - mid),
+            dest: v_mid,
+        };
+        let mut out = v_end;
+        while v < hole.dest && buf < hole.end {
+            // take from the right run on ties to stay stable
+            let to_copy = if is_less(&*hole.end.sub(1), &*hole.dest.sub(1)) {
+                hole.dest = hole.dest.sub(1);
+                hole.dest
+            } else {
+                hole.end = hole.end.sub(1);
+                hole.end
+            };
+            out = out.sub(1);
+            ptr::copy_nonoverlapping(to_copy, out, 1);
+        }
+    }
+    // the remaining [start, end) of buf goes to dest
+}
+
+struct MergeHole<T> {
+    start: *mut T,
+    end: *mut T,
+    dest: *mut T,
+}
+
+impl<T> Drop for MergeHole<T> {
+    fn drop(&mut self) {
+        unsafe {
+            let len = self.end.offset_from(self.start) as usize;
+            ptr::copy_nonoverlapping(self.start, self.dest, len);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    /// small xorshift, so the tests don't depend on rand
+    fn pseudo_random(len: usize, seed: u32) -> Vec<u32> {
+        let mut x = seed;
+        (0..len)
+            .map(|_| {
+                x ^= x << 13;
+                x ^= x >> 17;
+                x ^= x << 5;
+                x % 64
+            })
+            .collect()
+    }
+
+    #[test]
+    fn try_sort() {
+        for len in [0, 1, 2, 19, 20, 21, 100, 1000] {
+            let v = pseudo_random(len, len as u32 + 1);
+            let mut expected = v.clone();
+            expected.sort();
+
+            let mut a = v.clone();
+            a.try_sort().unwrap();
+            assert_eq!(a, expected);
+
+            let mut a = v.clone();
+            a.try_sort_by(|a, b| b.cmp(a)).unwrap();
+            assert!(a.iter().eq(expected.iter().rev()));
+
+            // stability: pair each value with its position and sort on value only
+            let pairs: Vec<(u32, usize)> = v.iter().copied().zip(0..).collect();
+            let mut expected = pairs.clone();
+            expected.sort_by_key(|p| p.0 / 4);
+
+            let mut a = pairs.clone();
+            a.try_sort_by_key(|p| p.0 / 4).unwrap();
+            assert_eq!(a, expected);
+
+            let mut a = pairs.clone();
+            a.try_sort_by_cached_key(|p| p.0 / 4).unwrap();
+            assert_eq!(a, expected);
+        }
+
+        let mut zst = [(); 50];
+        zst.try_sort().unwrap();
+    }
+
+    #[test]
+    fn try_sort_panic_keeps_elements() {
+        let mut v: Vec<Vec<u32>> = pseudo_random(200, 7).into_iter().map(|x| vec![x]).collect();
+        let calls = Cell::new(0);
+        let r = catch_unwind(AssertUnwindSafe(|| {
+            v.try_sort_by(|a, b| {
+                calls.set(calls.get() + 1);
+                if calls.get() == 500 {
+                    panic!("compare");
+                }
+                a.cmp(b)
+            })
+        }));
+        assert!(r.is_err());
+        let mut sorted: Vec<u32> = v.iter().map(|x| x[0]).collect();
+        sorted.sort();
+        let mut expected = pseudo_random(200, 7);
+        expected.sort();
+        assert_eq!(sorted, expected);
+    }
+}
@@ -0,0 +1,565 @@
+//! Implement a Fallible String
+use super::FallibleVec;
+use super::TryClone;
+use crate::vec::{needs_to_grow, vec_try_reserve_for_growth};
+use crate::TryReserveError;
+use crate::TryVec;
+use crate::{TryExtend, TryFromIterator};
+use alloc::string::{FromUtf8Error, String};
+use core::ops::{Bound, RangeBounds};
+
+/// trait implementing all fallible methods on string
+pub trait FallibleString {
+    /// see reserve
+    fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError>;
+    /// see with capacity, (Self must be sized by the constraint of Result)
+    fn try_with_capacity(capacity: usize) -> Result<Self, TryReserveError>
+    where
+        Self: core::marker::Sized;
+    /// see push
+    fn try_push(&mut self, ch: char) -> Result<(), TryReserveError>;
+    /// see push_str
+    fn try_push_str(&mut self, string: &str) -> Result<(), TryReserveError>;
+    /// see insert
+    fn try_insert(&mut self, idx: usize, ch: char) -> Result<(), TryReserveError>;
+    /// see insert_str
+    fn try_insert_str(&mut self, idx: usize, string: &str) -> Result<(), TryReserveError>;
+    /// push all the chars of `iter`, on error the chars already pushed are kept
+    fn try_extend_from_chars<I: IntoIterator<Item = char>>(
+        &mut self,
+        iter: I,
+    ) -> Result<(), TryReserveError>;
+    /// see replace_range, the string is left untouched on error
+    fn try_replace_range<R: RangeBounds<usize>>(
+        &mut self,
+        range: R,
+        replace_with: &str,
+    ) -> Result<(), TryReserveError>;
+}
+
+/// amortized reservation, as done by FallibleVec::try_push
+#[inline]
+fn string_try_reserve_for_growth(s: &mut String, additional: usize) -> Result<(), TryReserveError> {
+    // reserving doesn't touch the content, so the string stays utf8
+    let v = unsafe { s.as_mut_vec() };
+    if needs_to_grow(v, additional) {
+        vec_try_reserve_for_growth(v, additional)?;
+    }
+    Ok(())
+}
+
+impl FallibleString for String {
+    #[inline]
+    fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        // reserving doesn't touch the content, so the string stays utf8
+        FallibleVec::try_reserve(unsafe { self.as_mut_vec() }, additional)
+    }
+
+    #[inline]
+    fn try_with_capacity(capacity: usize) -> Result<Self, TryReserveError> {
+        let v = FallibleVec::try_with_capacity(capacity)?;
+        // an empty vec is valid utf8
+        Ok(unsafe { String::from_utf8_unchecked(v) })
+    }
+
+    #[inline]
+    fn try_push(&mut self, ch: char) -> Result<(), TryReserveError> {
+        string_try_reserve_for_growth(self, ch.len_utf8())?;
+        self.push(ch);
+        Ok(())
+    }
+
+    #[inline]
+    fn try_push_str(&mut self, string: &str) -> Result<(), TryReserveError> {
+        string_try_reserve_for_growth(self, string.len())?;
+        self.push_str(string);
+        Ok(())
+    }
+
+    #[inline]
+    fn try_insert(&mut self, idx: usize, ch: char) -> Result<(), TryReserveError> {
+        assert!(self.is_char_boundary(idx));
+        string_try_reserve_for_growth(self, ch.len_utf8())?;
+        self.insert(idx, ch);
+        Ok(())
+    }
+
+    #[inline]
+    fn try_insert_str(&mut self, idx: usize, string: &str) -> Result<(), TryReserveError> {
+        assert!(self.is_char_boundary(idx));
+        string_try_reserve_for_growth(self, string.len())?;
+        self.insert_str(idx, string);
+        Ok(())
+    }
+
+    fn try_extend_from_chars<I: IntoIterator<Item = char>>(
+        &mut self,
+        iter: I,
+    ) -> Result<(), TryReserveError> {
+        let iter = iter.into_iter();
+        // every char is at least one byte long
+        FallibleString::try_reserve(self, iter.size_hint().0)?;
+        for ch in iter {
+            self.try_push(ch)?;
+        }
+        Ok(())
+    }
+
+    fn try_replace_range<R: RangeBounds<usize>>(
+        &mut self,
+        range: R,
+        replace_with: &str,
+    ) -> Result<(), TryReserveError> {
+        let start = match range.start_bound() {
+            Bound::Included(&n) => n,
+            Bound::Excluded(&n) => n.saturating_add(1),
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&n) => n.saturating_add(1),
+            Bound::Excluded(&n) => n,
+            Bound::Unbounded => self.len(),
+        };
+        // with the room reserved, the splice done by replace_range never
+        // reallocates. Invalid ranges are left to replace_range to panic on.
+        let removed = end.saturating_sub(start);
+        if replace_with.len() > removed {
+            string_try_reserve_for_growth(self, replace_with.len() - removed)?;
+        }
+        self.replace_range(range, replace_with);
+        Ok(())
+    }
+}
+
+impl TryExtend<char> for String {
+    #[inline(always)]
+    fn try_extend<I: IntoIterator<Item = char>>(&mut self, iter: I) -> Result<(), TryReserveError> {
+        FallibleString::try_extend_from_chars(self, iter)
+    }
+}
+
+impl<'a> TryExtend<&'a char> for String {
+    #[inline]
+    fn try_extend<I: IntoIterator<Item = &'a char>>(
+        &mut self,
+        iter: I,
+    ) -> Result<(), TryReserveError> {
+        self.try_extend(iter.into_iter().copied())
+    }
+}
+
+impl<'a> TryExtend<&'a str> for String {
+    fn try_extend<I: IntoIterator<Item = &'a str>>(
+        &mut self,
+        iter: I,
+    ) -> Result<(), TryReserveError> {
+        for s in iter {
+            self.try_push_str(s)?;
+        }
+        Ok(())
+    }
+}
+
+impl TryFromIterator<char> for String {
+    #[inline]
+    fn try_from_iterator<T: IntoIterator<Item = char>>(
+        iterator: T,
+    ) -> Result<Self, TryReserveError> {
+        let mut s = String::new();
+        s.try_extend(iterator)?;
+        Ok(s)
+    }
+}
+
+impl<'a> TryFromIterator<&'a str> for String {
+    #[inline]
+    fn try_from_iterator<T: IntoIterator<Item = &'a str>>(
+        iterator: T,
+    ) -> Result<Self, TryReserveError> {
+        let mut s = String::new();
+        s.try_extend(iterator)?;
+        Ok(s)
+    }
+}
+
+/// TryString is a thin wrapper around alloc::string::String to provide
+/// support for fallible allocation.
+///
+/// See the crate documentation for more.
+/// # Examples
+///
+/// ```
+/// use fallible_collections::TryString;
+/// use core::convert::TryFrom;
+///
+/// let mut s = TryString::try_from("hello").unwrap();
+/// s.try_push(' ').unwrap();
+/// s.try_push_str("world").unwrap();
+/// s.try_insert_str(0, ">> ").unwrap();
+/// assert_eq!(s, ">> hello world");
+/// ```
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct TryString {
+    inner: String,
+}
+
+impl TryString {
+    #[inline(always)]
+    pub fn new() -> Self {
+        Self {
+            inner: String::new(),
+        }
+    }
+
+    #[inline]
+    pub fn try_with_capacity(capacity: usize) -> Result<Self, TryReserveError> {
+        Ok(Self {
+            inner: FallibleString::try_with_capacity(capacity)?,
+        })
+    }
+
+    /// Checked conversion, the bytes are validated as utf8 without any
+    /// allocation. On error the bytes can be taken back from the
+    /// `FromUtf8Error`.
+    #[inline]
+    pub fn from_utf8(bytes: TryVec<u8>) -> Result<Self, FromUtf8Error> {
+        Ok(Self {
+            inner: String::from_utf8(bytes.into_inner())?,
+        })
+    }
+
+    #[inline(always)]
+    pub fn as_str(&self) -> &str {
+        &self.inner
+    }
+
+    #[inline(always)]
+    pub fn as_mut_str(&mut self) -> &mut str {
+        &mut self.inner
+    }
+
+    #[inline(always)]
+    pub fn as_bytes(&self) -> &[u8] {
+        self.inner.as_bytes()
+    }
+
+    #[inline(always)]
+    pub fn capacity(&self) -> usize {
+        self.inner.capacity()
+    }
+
+    #[inline(always)]
+    pub fn clear(&mut self) {
+        self.inner.clear()
+    }
+
+    #[inline(always)]
+    pub fn into_bytes(self) -> TryVec<u8> {
+        self.inner.into_bytes().into()
+    }
+
+    #[inline(always)]
+    pub fn into_inner(self) -> String {
+        self.inner
+    }
+
+    #[inline(always)]
+    pub fn pop(&mut self) -> Option<char> {
+        self.inner.pop()
+    }
+
+    #[inline(always)]
+    pub fn remove(&mut self, idx: usize) -> char {
+        self.inner.remove(idx)
+    }
+
+    #[inline(always)]
+    pub fn truncate(&mut self, new_len: usize) {
+        self.inner.truncate(new_len)
+    }
+
+    #[inline(always)]
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        FallibleString::try_reserve(&mut self.inner, additional)
+    }
+
+    #[inline(always)]
+    pub fn try_push(&mut self, ch: char) -> Result<(), TryReserveError> {
+        self.inner.try_push(ch)
+    }
+
+    #[inline(always)]
+    pub fn try_push_str(&mut self, string: &str) -> Result<(), TryReserveError> {
+        self.inner.try_push_str(string)
+    }
+
+    /// Inserts a char at byte position `idx`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `idx` is larger than the string's length, or if it does
+    /// not lie on a char boundary.
+    #[inline(always)]
+    pub fn try_insert(&mut self, idx: usize, ch: char) -> Result<(), TryReserveError> {
+        self.inner.try_insert(idx, ch)
+    }
+
+    /// Inserts a string slice at byte position `idx`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `idx` is larger than the string's length, or if it does
+    /// not lie on a char boundary.
+    #[inline(always)]
+    pub fn try_insert_str(&mut self, idx: usize, string: &str) -> Result<(), TryReserveError> {
+        self.inner.try_insert_str(idx, string)
+    }
+
+    #[inline(always)]
+    pub fn try_replace_range<R: RangeBounds<usize>>(
+        &mut self,
+        range: R,
+        replace_with: &str,
+    ) -> Result<(), TryReserveError> {
+        self.inner.try_replace_range(range, replace_with)
+    }
+}
+
+impl TryClone for TryString {
+    #[inline]
+    fn try_clone(&self) -> Result<Self, TryReserveError> {
+        let mut s = Self::try_with_capacity(self.inner.len())?;
+        s.inner.push_str(&self.inner);
+        Ok(s)
+    }
+}
+
+impl core::fmt::Debug for TryString {
+    #[inline]
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.inner.fmt(f)
+    }
+}
+
+impl core::fmt::Display for TryString {
+    #[inline]
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.inner.fmt(f)
+    }
+}
+
+impl PartialEq<str> for TryString {
+    #[inline(always)]
+    fn eq(&self, other: &str) -> bool {
+        self.inner == other
+    }
+}
+
+impl PartialEq<&str> for TryString {
+    #[inline(always)]
+    fn eq(&self, other: &&str) -> bool {
+        self.inner == *other
+    }
+}
+
+impl PartialEq<String> for TryString {
+    #[inline(always)]
+    fn eq(&self, other: &String) -> bool {
+        self.inner == *other
+    }
+}
+
+impl core::convert::AsRef<str> for TryString {
+    #[inline(always)]
+    fn as_ref(&self) -> &str {
+        &self.inner
+    }
+}
+
+impl core::convert::AsRef<[u8]> for TryString {
+    #[inline(always)]
+    fn as_ref(&self) -> &[u8] {
+        self.inner.as_bytes()
+    }
+}
+
+impl core::convert::From<String> for TryString {
+    #[inline(always)]
+    fn from(value: String) -> Self {
+        Self { inner: value }
+    }
+}
+
+impl core::convert::TryFrom<&str> for TryString {
+    type Error = TryReserveError;
+
+    #[inline]
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let mut s = Self::try_with_capacity(value.len())?;
+        s.inner.push_str(value);
+        Ok(s)
+    }
+}
+
+impl core::convert::TryFrom<TryVec<u8>> for TryString {
+    type Error = FromUtf8Error;
+
+    #[inline(always)]
+    fn try_from(value: TryVec<u8>) -> Result<Self, Self::Error> {
+        Self::from_utf8(value)
+    }
+}
+
+impl core::ops::Deref for TryString {
+    type Target = str;
+
+    #[inline(always)]
+    fn deref(&self) -> &str {
+        &self.inner
+    }
+}
+
+impl core::ops::DerefMut for TryString {
+    #[inline(always)]
+    fn deref_mut(&mut self) -> &mut str {
+        &mut self.inner
+    }
+}
+
+impl TryExtend<char> for TryString {
+    #[inline(always)]
+    fn try_extend<I: IntoIterator<Item = char>>(&mut self, iter: I) -> Result<(), TryReserveError> {
+        self.inner.try_extend(iter)
+    }
+}
+
+impl<'a> TryExtend<&'a char> for TryString {
+    #[inline(always)]
+    fn try_extend<I: IntoIterator<Item = &'a char>>(
+        &mut self,
+        iter: I,
+    ) -> Result<(), TryReserveError> {
+        self.inner.try_extend(iter)
+    }
+}
+
+impl<'a> TryExtend<&'a str> for TryString {
+    #[inline(always)]
+    fn try_extend<I: IntoIterator<Item = &'a str>>(
+        &mut self,
+        iter: I,
+    ) -> Result<(), TryReserveError> {
+        self.inner.try_extend(iter)
+    }
+}
+
+impl TryFromIterator<char> for TryString {
+    #[inline]
+    fn try_from_iterator<T: IntoIterator<Item = char>>(
+        iterator: T,
+    ) -> Result<Self, TryReserveError> {
+        Ok(Self {
+            inner: String::try_from_iterator(iterator)?,
+        })
+    }
+}
+
+impl<'a> TryFromIterator<&'a str> for TryString {
+    #[inline]
+    fn try_from_iterator<T: IntoIterator<Item = &'a str>>(
+        iterator: T,
+    ) -> Result<Self, TryReserveError> {
+        Ok(Self {
+            inner: String::try_from_iterator(iterator)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::convert::TryFrom;
+
+    #[test]
+    fn trystring() {
+        let mut s = TryString::try_with_capacity(4).unwrap();
+        assert!(s.capacity() >= 4);
+        s.try_push('a').unwrap();
+        s.try_push('é').unwrap();
+        s.try_push_str("bc").unwrap();
+        s.try_insert_str(1, "--").unwrap();
+        assert_eq!(s, "a--ébc");
+        assert_eq!(s.as_str().len(), 7);
+        assert_eq!(s.chars().count(), 6);
+        assert!(s.try_reserve(usize::MAX).is_err());
+        assert_eq!(s.pop(), Some('c'));
+
+        let c = s.try_clone().unwrap();
+        assert_eq!(c, s);
+        assert_eq!(format!("{} {:?}", c, c), "a--éb \"a--éb\"");
+
+        let bytes = s.into_bytes();
+        assert_eq!(bytes, "a--éb");
+        assert_eq!(TryString::try_from(bytes).unwrap(), "a--éb");
+    }
+
+    #[test]
+    fn trystring_from_invalid_utf8() {
+        let bytes = TryVec::try_from(&[b'a', 0xff][..]).unwrap();
+        let err = TryString::from_utf8(bytes).unwrap_err();
+        assert_eq!(err.into_bytes(), [b'a', 0xff]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn trystring_insert_str_not_on_boundary() {
+        let mut s = TryString::try_from("é").unwrap();
+        let _ = s.try_insert_str(1, "a");
+    }
+
+    #[test]
+    fn fallible_string() {
+        let mut s = <String as FallibleString>::try_with_capacity(2).unwrap();
+        s.try_push('a').unwrap();
+        s.try_push_str("bcd").unwrap();
+        s.try_insert(0, 'é').unwrap();
+        s.try_insert_str(2, "__").unwrap();
+        assert_eq!(s, "é__abcd");
+        s.try_extend_from_chars("xy".chars()).unwrap();
+        s.try_replace_range(..2, "e").unwrap();
+        assert_eq!(s, "e__abcdxy");
+        s.try_replace_range(3..=5, "long replacement").unwrap();
+        assert_eq!(s, "e__long replacementdxy");
+        s.try_replace_range(1.., "").unwrap();
+        assert_eq!(s, "e");
+        assert!(FallibleString::try_reserve(&mut s, usize::MAX).is_err());
+        assert!(s.try_replace_range(.., &"x".repeat(2)).is_ok());
+        assert_eq!(s, "xx");
+    }
+
+    #[test]
+    fn trystring_collect() {
+        let mut s: TryString = TryFromIterator::try_from_iterator(vec!['a', 'b']).unwrap();
+        s.try_extend(vec!["cd", "e"]).unwrap();
+        assert_eq!(s, "abcde");
+    }
+
+    #[test]
+    fn try_collect_string() {
+        use crate::TryCollect;
+
+        let s: String = "héllo".chars().rev().try_collect().unwrap();
+        assert_eq!(s, "olléh");
+        let s: String = vec!["a", "bc", "", "d"].try_collect().unwrap();
+        assert_eq!(s, "abcd");
+    }
+
+    #[test]
+    fn try_extend_string() {
+        let mut s = String::from("a");
+        s.try_extend(vec!['b', 'é']).unwrap();
+        s.try_extend(['c'].iter()).unwrap();
+        s.try_extend(vec!["de", "f"]).unwrap();
+        assert_eq!(s, "abécdef");
+    }
+}
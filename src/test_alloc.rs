@@ -0,0 +1,75 @@
+//! Global allocator of the tests, failing the allocations of the current
+//! thread once its budget is spent, to exercise the error paths
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+struct BudgetAlloc;
+
+#[global_allocator]
+static ALLOC: BudgetAlloc = BudgetAlloc;
+
+thread_local! {
+    /// allocations the current thread can still do, const initialized
+    /// so that reading it never allocates
+    static BUDGET: Cell<usize> = const { Cell::new(usize::MAX) };
+}
+
+/// take one allocation from the budget, false if it is spent
+fn spend() -> bool {
+    BUDGET
+        .try_with(|budget| match budget.get() {
+            0 => false,
+            n => {
+                budget.set(n - 1);
+                true
+            }
+        })
+        .unwrap_or(true)
+}
+
+unsafe impl GlobalAlloc for BudgetAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if spend() {
+            System.alloc(layout)
+        } else {
+            core::ptr::null_mut()
+        }
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        if spend() {
+            System.alloc_zeroed(layout)
+        } else {
+            core::ptr::null_mut()
+        }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        if spend() {
+            System.realloc(ptr, layout, new_size)
+        } else {
+            core::ptr::null_mut()
+        }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+/// restores an unlimited budget, even if `f` panics
+struct Reset;
+
+impl Drop for Reset {
+    fn drop(&mut self) {
+        BUDGET.with(|budget| budget.set(usize::MAX));
+    }
+}
+
+/// run `f` with only `allocations` allowed on the current thread, every
+/// allocation after them fails
+pub(crate) fn with_alloc_budget<R>(allocations: usize, f: impl FnOnce() -> R) -> R {
+    let _reset = Reset;
+    BUDGET.with(|budget| budget.set(allocations));
+    f()
+}
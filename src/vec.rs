@@ -1,5 +1,6 @@
 //! Implement Fallible Vec
 use super::TryClone;
+use crate::FallibleSlice;
 use crate::TryReserveError;
 #[allow(unused_imports)]
 use alloc::alloc::{alloc, realloc, Layout};
@@ -58,6 +59,26 @@ pub trait FallibleVec<T> {
     fn try_extend_from_slice_no_copy(&mut self, other: &[T]) -> Result<(), TryReserveError>
     where
         T: TryClone;
+    /// extend the vec by trying to clone the values in `other`, on failure
+    /// the vec is truncated back to its original length
+    fn try_extend_from_slice_atomic(&mut self, other: &[T]) -> Result<(), TryReserveError>
+    where
+        T: TryClone;
+    /// extend the vec with the values of `iter`, on failure the vec is
+    /// truncated back to its original length, dropping the values already pushed
+    fn try_extend_atomic<I: IntoIterator<Item = T>>(
+        &mut self,
+        iter: I,
+    ) -> Result<(), TryReserveError>;
+    /// see split_off, the vec is left untouched if the tail can't be allocated
+    fn try_split_off(&mut self, at: usize) -> Result<Self, TryReserveError>
+    where
+        Self: core::marker::Sized;
+    /// try split off and give back ownership of the vec in case of error,
+    /// on success the left part keeps the original allocation
+    fn try_split_off_give_back(self, at: usize) -> Result<(Self, Self), (Self, TryReserveError)>
+    where
+        Self: core::marker::Sized;
 }
 
 /// TryVec is a thin wrapper around alloc::vec::Vec to provide support for
@@ -113,13 +134,66 @@ impl<T> TryVec<T> {
         self
     }
 
+    #[inline(always)]
+    pub fn capacity(&self) -> usize {
+        self.inner.capacity()
+    }
+
     #[inline(always)]
     pub fn clear(&mut self) {
         self.inner.clear()
     }
 
-    #[cfg(test)]
-    pub fn into_inner(self) -> Vec<T> {
+    #[inline(always)]
+    pub fn dedup_by<F>(&mut self, same_bucket: F)
+    where
+        F: FnMut(&mut T, &mut T) -> bool,
+    {
+        self.inner.dedup_by(same_bucket)
+    }
+
+    #[inline(always)]
+    pub fn dedup_by_key<K, F>(&mut self, key: F)
+    where
+        F: FnMut(&mut T) -> K,
+        K: PartialEq,
+    {
+        self.inner.dedup_by_key(key)
+    }
+
+    #[inline(always)]
+    pub fn drain<R>(&mut self, range: R) -> alloc::vec::Drain<'_, T>
+    where
+        R: core::ops::RangeBounds<usize>,
+    {
+        self.inner.drain(range)
+    }
+
+    /// Appends every element of the iterator, reserving the lower bound of
+    /// its size hint up front. On allocation failure, the elements pushed
+    /// so far are kept and the rest of the iterator is dropped.
+    #[inline(always)]
+    pub fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) -> Result<(), TryReserveError> {
+        TryExtend::try_extend(&mut self.inner, iter)
+    }
+
+    /// Same as `extend`, but on allocation failure the vector is truncated
+    /// back to its original length, dropping the elements already pushed.
+    #[inline]
+    pub fn extend_atomic<I: IntoIterator<Item = T>>(
+        &mut self,
+        iter: I,
+    ) -> Result<(), TryReserveError> {
+        FallibleVec::try_extend_atomic(&mut self.inner, iter)
+    }
+
+    #[inline]
+    pub fn insert(&mut self, index: usize, element: T) -> Result<(), TryReserveError> {
+        FallibleVec::try_insert(&mut self.inner, index, element).map_err(|(_, e)| e)
+    }
+
+    #[inline(always)]
+    pub(crate) fn into_inner(self) -> Vec<T> {
         self.inner
     }
 
@@ -129,14 +203,14 @@ impl<T> TryVec<T> {
     }
 
     #[inline(always)]
-    pub fn iter_mut(&mut self) -> IterMut<T> {
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
         IterMut {
             inner: self.inner.iter_mut(),
         }
     }
 
     #[inline(always)]
-    pub fn iter(&self) -> Iter<T> {
+    pub fn iter(&self) -> Iter<'_, T> {
         Iter {
             inner: self.inner.iter(),
         }
@@ -152,6 +226,11 @@ impl<T> TryVec<T> {
         FallibleVec::try_push(&mut self.inner, value)
     }
 
+    #[inline(always)]
+    pub fn remove(&mut self, index: usize) -> T {
+        self.inner.remove(index)
+    }
+
     #[inline(always)]
     pub fn reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
         FallibleVec::try_reserve(&mut self.inner, additional)
@@ -164,6 +243,92 @@ impl<T> TryVec<T> {
     {
         FallibleVec::try_resize_with(&mut self.inner, new_len, f)
     }
+
+    #[inline(always)]
+    pub fn retain<F>(&mut self, f: F)
+    where
+        F: FnMut(&T) -> bool,
+    {
+        self.inner.retain(f)
+    }
+
+    /// Splits the vector in two at `at`, the returned vector holding the
+    /// elements `[at, len)`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `at > len`.
+    ///
+    /// The vector is left untouched if the tail can't be allocated.
+    #[inline]
+    pub fn split_off(&mut self, at: usize) -> Result<Self, TryReserveError> {
+        Ok(Self {
+            inner: FallibleVec::try_split_off(&mut self.inner, at)?,
+        })
+    }
+
+    /// Same as `split_off`, but takes the vector by value and gives it back
+    /// in case of error. On success the left part keeps the original
+    /// allocation.
+    #[inline]
+    pub fn split_off_give_back(self, at: usize) -> Result<(Self, Self), (Self, TryReserveError)> {
+        match FallibleVec::try_split_off_give_back(self.inner, at) {
+            Ok((left, right)) => Ok((Self { inner: left }, Self { inner: right })),
+            Err((inner, e)) => Err((Self { inner }, e)),
+        }
+    }
+
+    #[inline(always)]
+    pub fn swap_remove(&mut self, index: usize) -> T {
+        self.inner.swap_remove(index)
+    }
+
+    #[inline(always)]
+    pub fn truncate(&mut self, len: usize) {
+        self.inner.truncate(len)
+    }
+
+    /// Stable sort, see `FallibleSlice::try_sort_by`
+    #[inline(always)]
+    pub fn try_sort_by<F>(&mut self, compare: F) -> Result<(), TryReserveError>
+    where
+        F: FnMut(&T, &T) -> core::cmp::Ordering,
+    {
+        self.inner.try_sort_by(compare)
+    }
+
+    /// Stable sort, see `FallibleSlice::try_sort_by_key`
+    #[inline(always)]
+    pub fn try_sort_by_key<K: Ord, F: FnMut(&T) -> K>(
+        &mut self,
+        f: F,
+    ) -> Result<(), TryReserveError> {
+        self.inner.try_sort_by_key(f)
+    }
+
+    /// Stable sort, see `FallibleSlice::try_sort_by_cached_key`
+    #[inline(always)]
+    pub fn try_sort_by_cached_key<K: Ord, F: FnMut(&T) -> K>(
+        &mut self,
+        f: F,
+    ) -> Result<(), TryReserveError> {
+        self.inner.try_sort_by_cached_key(f)
+    }
+}
+
+impl<T: Ord> TryVec<T> {
+    /// Stable sort, see `FallibleSlice::try_sort`
+    #[inline(always)]
+    pub fn try_sort(&mut self) -> Result<(), TryReserveError> {
+        self.inner.try_sort()
+    }
+}
+
+impl<T: PartialEq> TryVec<T> {
+    #[inline(always)]
+    pub fn dedup(&mut self) {
+        self.inner.dedup()
+    }
 }
 
 impl<T: TryClone> TryClone for TryVec<T> {
@@ -189,6 +354,13 @@ impl<T: TryClone> TryVec<T> {
     pub fn extend_from_slice(&mut self, other: &[T]) -> Result<(), TryReserveError> {
         self.inner.try_extend_from_slice_no_copy(other)
     }
+
+    /// Same as `extend_from_slice`, but if cloning an element fails, the
+    /// vector is truncated back to its original length.
+    #[inline(always)]
+    pub fn extend_from_slice_atomic(&mut self, other: &[T]) -> Result<(), TryReserveError> {
+        self.inner.try_extend_from_slice_atomic(other)
+    }
 }
 
 impl<T> IntoIterator for TryVec<T> {
@@ -299,7 +471,7 @@ pub mod std_io {
 
         #[test]
         fn read_into_try_vec_oom() {
-            let mut src = b"1234567890".take(core::usize::MAX.try_into().expect("usize < u64"));
+            let mut src = b"1234567890".take(usize::MAX.try_into().expect("usize < u64"));
             assert!(src.read_into_try_vec().is_err());
         }
 
@@ -316,7 +488,7 @@ pub mod std_io {
         fn try_read_up_to_oom() {
             let src = b"1234567890";
             let mut buf = TryVec::new();
-            let limit = core::usize::MAX.try_into().expect("usize < u64");
+            let limit = usize::MAX.try_into().expect("usize < u64");
             let res = super::try_read_up_to(&mut src.as_ref(), limit, &mut buf);
             assert!(res.is_err());
         }
@@ -330,7 +502,7 @@ impl<T: PartialEq> PartialEq<Vec<T>> for TryVec<T> {
     }
 }
 
-impl<'a, T: PartialEq> PartialEq<&'a [T]> for TryVec<T> {
+impl<T: PartialEq> PartialEq<&[T]> for TryVec<T> {
     #[inline(always)]
     fn eq(&self, other: &&[T]) -> bool {
         self.inner.eq(other)
@@ -433,13 +605,16 @@ impl<'a, T> Iterator for IterMut<'a, T> {
 
 /// Grow capacity exponentially
 #[cold]
-fn vec_try_reserve_for_growth<T>(v: &mut Vec<T>, additional: usize) -> Result<(), TryReserveError> {
+pub(crate) fn vec_try_reserve_for_growth<T>(
+    v: &mut Vec<T>,
+    additional: usize,
+) -> Result<(), TryReserveError> {
     // saturating, because can't use CapacityOverflow here if rust_1_57 flag is enabled
     FallibleVec::try_reserve(v, additional.max(v.capacity().saturating_mul(2) - v.len()))
 }
 
-fn needs_to_grow<T>(v: &Vec<T>, len: usize) -> bool {
-    v.len().checked_add(len).map_or(true, |needed| needed > v.capacity())
+pub(crate) fn needs_to_grow<T>(v: &Vec<T>, len: usize) -> bool {
+    len > v.capacity() - v.len()
 }
 
 #[cfg(not(any(feature = "unstable", feature = "rust_1_57")))]
@@ -515,7 +690,7 @@ impl<T> FallibleVec<T> for Vec<T> {
             self.try_reserve(additional)
         }
 
-        #[cfg(not(feature = "rust_1_57"))]
+        #[cfg(not(any(feature = "unstable", feature = "rust_1_57")))]
         {
             vec_try_reserve(self, additional)
         }
@@ -534,7 +709,8 @@ impl<T> FallibleVec<T> for Vec<T> {
         if self.len() == self.capacity() {
             vec_try_reserve_for_growth(self, 1)?;
         }
-        Ok(self.push(elem))
+        self.push(elem);
+        Ok(())
     }
 
     #[inline]
@@ -544,7 +720,8 @@ impl<T> FallibleVec<T> for Vec<T> {
                 return Err((elem, e));
             }
         }
-        Ok(self.push(elem))
+        self.push(elem);
+        Ok(())
     }
 
     #[inline]
@@ -564,12 +741,14 @@ impl<T> FallibleVec<T> for Vec<T> {
                 return Err((element, e));
             }
         }
-        Ok(self.insert(index, element))
+        self.insert(index, element);
+        Ok(())
     }
     #[inline]
     fn try_append(&mut self, other: &mut Self) -> Result<(), TryReserveError> {
         FallibleVec::try_reserve(self, other.len())?;
-        Ok(self.append(other))
+        self.append(other);
+        Ok(())
     }
     fn try_resize(&mut self, new_len: usize, value: T) -> Result<(), TryReserveError>
     where
@@ -579,7 +758,8 @@ impl<T> FallibleVec<T> for Vec<T> {
         if new_len > len {
             FallibleVec::try_reserve(self, new_len - len)?;
         }
-        Ok(self.resize(new_len, value))
+        self.resize(new_len, value);
+        Ok(())
     }
     fn try_resize_with<F>(&mut self, new_len: usize, f: F) -> Result<(), TryReserveError>
     where
@@ -589,7 +769,8 @@ impl<T> FallibleVec<T> for Vec<T> {
         if new_len > len {
             FallibleVec::try_reserve(self, new_len - len)?;
         }
-        Ok(self.resize_with(new_len, f))
+        self.resize_with(new_len, f);
+        Ok(())
     }
     fn try_resize_no_copy(&mut self, new_len: usize, value: T) -> Result<(), TryReserveError>
     where
@@ -600,7 +781,8 @@ impl<T> FallibleVec<T> for Vec<T> {
         if new_len > len {
             self.try_extend_with(new_len - len, TryExtendElement(value))
         } else {
-            Ok(self.truncate(new_len))
+            self.truncate(new_len);
+            Ok(())
         }
     }
     #[inline]
@@ -611,7 +793,8 @@ impl<T> FallibleVec<T> for Vec<T> {
         if needs_to_grow(self, other.len()) {
             vec_try_reserve_for_growth(self, other.len())?;
         }
-        Ok(self.extend_from_slice(other))
+        self.extend_from_slice(other);
+        Ok(())
     }
     fn try_extend_from_slice_no_copy(&mut self, other: &[T]) -> Result<(), TryReserveError>
     where
@@ -621,10 +804,9 @@ impl<T> FallibleVec<T> for Vec<T> {
             vec_try_reserve_for_growth(self, other.len())?;
         }
         let mut len = self.len();
-        let mut iterator = other.iter();
-        while let Some(element) = iterator.next() {
+        for element in other {
             unsafe {
-                core::ptr::write(self.get_unchecked_mut(len), element.try_clone()?);
+                core::ptr::write(self.as_mut_ptr().add(len), element.try_clone()?);
                 // NB can't overflow since we would have had to alloc the address space
                 len += 1;
                 self.set_len(len);
@@ -632,6 +814,48 @@ impl<T> FallibleVec<T> for Vec<T> {
         }
         Ok(())
     }
+    fn try_extend_from_slice_atomic(&mut self, other: &[T]) -> Result<(), TryReserveError>
+    where
+        T: TryClone,
+    {
+        let len = self.len();
+        let result = self.try_extend_from_slice_no_copy(other);
+        if result.is_err() {
+            self.truncate(len);
+        }
+        result
+    }
+    fn try_extend_atomic<I: IntoIterator<Item = T>>(
+        &mut self,
+        iter: I,
+    ) -> Result<(), TryReserveError> {
+        let iter = iter.into_iter();
+        FallibleVec::try_reserve(self, iter.size_hint().0)?;
+        let len = self.len();
+        for element in iter {
+            if let Err(e) = FallibleVec::try_push(self, element) {
+                self.truncate(len);
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+    fn try_split_off(&mut self, at: usize) -> Result<Self, TryReserveError> {
+        assert!(at <= self.len(), "`at` split index out of bounds");
+        let mut other = <Self as FallibleVec<T>>::try_with_capacity(self.len() - at)?;
+        // the capacity is already there, so extend won't reallocate
+        other.extend(self.drain(at..));
+        Ok(other)
+    }
+    fn try_split_off_give_back(
+        mut self,
+        at: usize,
+    ) -> Result<(Self, Self), (Self, TryReserveError)> {
+        match FallibleVec::try_split_off(&mut self, at) {
+            Ok(other) => Ok((self, other)),
+            Err(e) => Err((self, e)),
+        }
+    }
 }
 
 trait ExtendWith<T> {
@@ -651,7 +875,7 @@ impl<T: TryClone> ExtendWith<T> for TryExtendElement<T> {
     }
 }
 
-trait TryExtend<T> {
+trait TryExtendWith<T> {
     fn try_extend_with<E: ExtendWith<T>>(
         &mut self,
         n: usize,
@@ -659,7 +883,7 @@ trait TryExtend<T> {
     ) -> Result<(), TryReserveError>;
 }
 
-impl<T> TryExtend<T> for Vec<T> {
+impl<T> TryExtendWith<T> for Vec<T> {
     /// Extend the vector by `n` values, using the given generator.
     fn try_extend_with<E: ExtendWith<T>>(
         &mut self,
@@ -696,32 +920,6 @@ impl<T> TryExtend<T> for Vec<T> {
     }
 }
 
-trait Truncate {
-    fn truncate(&mut self, len: usize);
-}
-
-impl<T> Truncate for Vec<T> {
-    fn truncate(&mut self, len: usize) {
-        let current_len = self.len();
-        unsafe {
-            let mut ptr = self.as_mut_ptr().add(current_len);
-            // Set the final length at the end, keeping in mind that
-            // dropping an element might panic. Works around a missed
-            // optimization, as seen in the following issue:
-            // https://github.com/rust-lang/rust/issues/51802
-            let mut local_len = self.len();
-
-            // drop any extra elements
-            for _ in len..current_len {
-                ptr = ptr.offset(-1);
-                core::ptr::drop_in_place(ptr);
-                local_len -= 1;
-                self.set_len(local_len);
-            }
-        }
-    }
-}
-
 /// try creating a vec from an `elem` cloned `n` times, see std::from_elem
 #[cfg(feature = "unstable")]
 pub fn try_from_elem<T: TryClone>(elem: T, n: usize) -> Result<Vec<T>, TryReserveError> {
@@ -773,18 +971,71 @@ pub trait TryFromIterator<I>: Sized {
 }
 
 impl<I> TryFromIterator<I> for Vec<I> {
-    fn try_from_iterator<T: IntoIterator<Item = I>>(iterator: T) -> Result<Self, TryReserveError>
-    where
-        T: IntoIterator<Item = I>,
-    {
+    fn try_from_iterator<T: IntoIterator<Item = I>>(iterator: T) -> Result<Self, TryReserveError> {
         let mut new = Self::new();
-        for i in iterator {
-            new.try_push(i)?;
-        }
+        new.try_extend(iterator)?;
         Ok(new)
     }
 }
 
+impl<I> TryFromIterator<I> for TryVec<I> {
+    #[inline]
+    fn try_from_iterator<T: IntoIterator<Item = I>>(iterator: T) -> Result<Self, TryReserveError> {
+        Ok(Self {
+            inner: Vec::try_from_iterator(iterator)?,
+        })
+    }
+}
+
+/// Fallible counterpart of `core::iter::Extend`, returning an error
+/// instead of panicking when the collection can't allocate. On error, the
+/// items added so far are kept and the rest of the iterator is dropped.
+pub trait TryExtend<A> {
+    /// Adds the items of `iter` to the collection. On error the items
+    /// already added are kept, so the collection may be partially
+    /// extended. Use `FallibleVec::try_extend_atomic` or
+    /// `TryVec::extend_atomic` to leave a vec unchanged on error instead.
+    fn try_extend<I: IntoIterator<Item = A>>(&mut self, iter: I) -> Result<(), TryReserveError>;
+}
+
+impl<T> TryExtend<T> for Vec<T> {
+    fn try_extend<I: IntoIterator<Item = T>>(&mut self, iter: I) -> Result<(), TryReserveError> {
+        let iter = iter.into_iter();
+        FallibleVec::try_reserve(self, iter.size_hint().0)?;
+        for element in iter {
+            self.try_push(element)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a, T: Copy + 'a> TryExtend<&'a T> for Vec<T> {
+    #[inline]
+    fn try_extend<I: IntoIterator<Item = &'a T>>(
+        &mut self,
+        iter: I,
+    ) -> Result<(), TryReserveError> {
+        self.try_extend(iter.into_iter().copied())
+    }
+}
+
+impl<T> TryExtend<T> for TryVec<T> {
+    #[inline(always)]
+    fn try_extend<I: IntoIterator<Item = T>>(&mut self, iter: I) -> Result<(), TryReserveError> {
+        self.inner.try_extend(iter)
+    }
+}
+
+impl<'a, T: Copy + 'a> TryExtend<&'a T> for TryVec<T> {
+    #[inline(always)]
+    fn try_extend<I: IntoIterator<Item = &'a T>>(
+        &mut self,
+        iter: I,
+    ) -> Result<(), TryReserveError> {
+        self.inner.try_extend(iter)
+    }
+}
+
 pub trait TryCollect<I> {
     fn try_collect<C: TryFromIterator<I>>(self) -> Result<C, TryReserveError>;
 }
@@ -799,6 +1050,105 @@ where
     }
 }
 
+/// Error returned by `try_collect_results`, telling an error item of the
+/// iterator apart from an allocation failure of the collection
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CollectError<E> {
+    /// the iterator yielded this error, collecting stopped there
+    Item(E),
+    /// the collection couldn't allocate
+    Alloc(TryReserveError),
+}
+
+impl<E> From<TryReserveError> for CollectError<E> {
+    #[inline(always)]
+    fn from(e: TryReserveError) -> Self {
+        CollectError::Alloc(e)
+    }
+}
+
+impl<E: core::fmt::Display> core::fmt::Display for CollectError<E> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            CollectError::Item(e) => e.fmt(f),
+            // the error of hashbrown can't be displayed
+            CollectError::Alloc(_) => f.write_str("memory allocation failed"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<E: std::error::Error> std::error::Error for CollectError<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            // displayed as is, so its source is ours
+            CollectError::Item(e) => e.source(),
+            CollectError::Alloc(_) => None,
+        }
+    }
+}
+
+/// Collect an iterator of `Result` into any `TryFromIterator`, stopping at
+/// the first error item
+pub trait TryCollectResults<T, E> {
+    fn try_collect_results<C: TryFromIterator<T>>(self) -> Result<C, CollectError<E>>;
+}
+
+impl<I, T, E> TryCollectResults<T, E> for I
+where
+    I: IntoIterator<Item = Result<T, E>>,
+{
+    fn try_collect_results<C: TryFromIterator<T>>(self) -> Result<C, CollectError<E>> {
+        let mut error = None;
+        let collection = C::try_from_iterator(ResultShunt {
+            iter: self.into_iter(),
+            error: &mut error,
+        })?;
+        match error {
+            Some(e) => Err(CollectError::Item(e)),
+            None => Ok(collection),
+        }
+    }
+}
+
+/// Yields the `Ok` values of the iterator until the first `Err`, which is
+/// stored aside
+struct ResultShunt<'a, I, E> {
+    iter: I,
+    error: &'a mut Option<E>,
+}
+
+impl<I, T, E> Iterator for ResultShunt<'_, I, E>
+where
+    I: Iterator<Item = Result<T, E>>,
+{
+    type Item = T;
+
+    #[inline]
+    fn next(&mut self) -> Option<T> {
+        if self.error.is_some() {
+            return None;
+        }
+        match self.iter.next()? {
+            Ok(value) => Some(value),
+            Err(e) => {
+                *self.error = Some(e);
+                None
+            }
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.error.is_some() {
+            (0, Some(0))
+        } else {
+            // any item may be an error, so there is no lower bound
+            (0, self.iter.size_hint().1)
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -824,16 +1174,18 @@ mod tests {
     #[test]
     fn try_clone_oom() {
         let layout = Layout::new::<u8>();
-        let v =
-            unsafe { Vec::<u8>::from_raw_parts(alloc(layout), core::isize::MAX as usize, core::isize::MAX as usize) };
+        let v = unsafe {
+            Vec::<u8>::from_raw_parts(alloc(layout), isize::MAX as usize, isize::MAX as usize)
+        };
         assert!(v.try_clone().is_err());
     }
 
     #[test]
     fn tryvec_try_clone_oom() {
         let layout = Layout::new::<u8>();
-        let inner =
-            unsafe { Vec::<u8>::from_raw_parts(alloc(layout), core::isize::MAX as usize, core::isize::MAX as usize) };
+        let inner = unsafe {
+            Vec::<u8>::from_raw_parts(alloc(layout), isize::MAX as usize, isize::MAX as usize)
+        };
         let tv = TryVec { inner };
         assert!(tv.try_clone().is_err());
     }
@@ -847,27 +1199,25 @@ mod tests {
     #[test]
     fn oom() {
         let mut vec: Vec<char> = Vec::new();
-        match FallibleVec::try_reserve(&mut vec, core::usize::MAX / std::mem::size_of::<char>()) {
-            Ok(_) => panic!("it should be OOM"),
-            _ => (),
-        }
-        match FallibleVec::try_reserve(&mut vec, core::usize::MAX) {
-            Ok(_) => panic!("it should be OOM"),
-            _ => (),
-        }
+        assert!(
+            FallibleVec::try_reserve(&mut vec, usize::MAX / std::mem::size_of::<char>()).is_err(),
+            "it should be OOM"
+        );
+        assert!(
+            FallibleVec::try_reserve(&mut vec, usize::MAX).is_err(),
+            "it should be OOM"
+        );
     }
 
     #[test]
     fn tryvec_oom() {
         let mut vec: TryVec<char> = TryVec::new();
-        match vec.reserve(core::usize::MAX / std::mem::size_of::<char>()) {
-            Ok(_) => panic!("it should be OOM"),
-            _ => (),
-        }
-        match vec.reserve(core::usize::MAX) {
-            Ok(_) => panic!("it should be OOM"),
-            _ => (),
-        }
+        assert!(
+            vec.reserve(usize::MAX / std::mem::size_of::<char>())
+                .is_err(),
+            "it should be OOM"
+        );
+        assert!(vec.reserve(usize::MAX).is_err(), "it should be OOM");
     }
 
     #[test]
@@ -914,19 +1264,19 @@ mod tests {
     #[test]
     fn capacity_overflow() {
         let mut vec: Vec<_> = vec![1];
-        match FallibleVec::try_reserve(&mut vec, core::usize::MAX) {
-            Ok(_) => panic!("capacity calculation should overflow"),
-            _ => (),
-        }
+        assert!(
+            FallibleVec::try_reserve(&mut vec, usize::MAX).is_err(),
+            "capacity calculation should overflow"
+        );
     }
 
     #[test]
     fn tryvec_capacity_overflow() {
         let mut vec: TryVec<_> = vec![1].into();
-        match vec.reserve(core::usize::MAX) {
-            Ok(_) => panic!("capacity calculation should overflow"),
-            _ => (),
-        }
+        assert!(
+            vec.reserve(usize::MAX).is_err(),
+            "capacity calculation should overflow"
+        );
     }
 
     #[test]
@@ -955,14 +1305,202 @@ mod tests {
     #[cfg(not(any(feature = "unstable", feature = "rust_1_57")))]
     fn try_extend_zst() {
         let mut vec: Vec<()> = Vec::new();
-        assert_eq!(vec.capacity(), core::usize::MAX);
+        assert_eq!(vec.capacity(), usize::MAX);
         assert!(vec_try_extend(&mut vec, 10).is_ok());
-        assert!(vec_try_extend(&mut vec, core::usize::MAX).is_ok());
+        assert!(vec_try_extend(&mut vec, usize::MAX).is_ok());
     }
 
     #[test]
     fn try_reserve_zst() {
         let mut vec: Vec<()> = Vec::new();
-        assert!(FallibleVec::try_reserve(&mut vec, core::usize::MAX).is_ok());
+        assert!(FallibleVec::try_reserve(&mut vec, usize::MAX).is_ok());
+    }
+
+    #[test]
+    fn tryvec_vec_parity() {
+        let mut vec = TryVec::new();
+        vec.extend(0..10).unwrap();
+        assert!(vec.capacity() >= 10);
+        vec.insert(0, 42).unwrap();
+        assert_eq!(vec.first(), Some(&42));
+        assert_eq!(vec.remove(0), 42);
+        assert_eq!(vec.swap_remove(0), 0);
+        assert_eq!(vec, vec![9, 1, 2, 3, 4, 5, 6, 7, 8]);
+
+        vec.retain(|x| x % 2 == 1);
+        assert_eq!(vec, vec![9, 1, 3, 5, 7]);
+        vec.truncate(3);
+        assert_eq!(vec.last(), Some(&3));
+
+        vec.extend(vec![3, 3, 4, 4, 4]).unwrap();
+        vec.dedup();
+        assert_eq!(vec, vec![9, 1, 3, 4]);
+        vec.dedup_by_key(|x| *x / 2);
+        assert_eq!(vec, vec![9, 1, 3, 4]);
+
+        let drained: Vec<_> = vec.drain(1..3).collect();
+        assert_eq!(drained, [1, 3]);
+        assert_eq!(vec, vec![9, 4]);
+
+        let mut vec: TryVec<_> = (0..10).collect::<Vec<_>>().into();
+        let tail = vec.split_off(6).unwrap();
+        assert_eq!(vec, vec![0, 1, 2, 3, 4, 5]);
+        assert_eq!(tail, vec![6, 7, 8, 9]);
+        assert!(vec.split_off(6).unwrap().is_empty());
+    }
+
+    #[test]
+    fn try_split_off() {
+        let mut vec: Vec<u32> = (0..10).collect();
+        let tail = vec.try_split_off(7).unwrap();
+        assert_eq!(vec, [0, 1, 2, 3, 4, 5, 6]);
+        assert_eq!(tail, [7, 8, 9]);
+
+        let ptr = vec.as_ptr();
+        let (left, right) = vec.try_split_off_give_back(2).unwrap();
+        assert_eq!(left.as_ptr(), ptr);
+        assert_eq!(left, [0, 1]);
+        assert_eq!(right, [2, 3, 4, 5, 6]);
+
+        let vec: TryVec<_> = left.into();
+        let (left, right) = vec.split_off_give_back(0).unwrap();
+        assert!(left.is_empty());
+        assert_eq!(right, vec![0, 1]);
+    }
+
+    /// fails to clone once its budget is spent
+    struct Flaky<'a>(&'a core::cell::Cell<usize>);
+
+    impl TryClone for Flaky<'_> {
+        fn try_clone(&self) -> Result<Self, TryReserveError> {
+            match self.0.get() {
+                0 => Err(FallibleVec::try_reserve(&mut Vec::<u8>::new(), usize::MAX).unwrap_err()),
+                budget => {
+                    self.0.set(budget - 1);
+                    Ok(Flaky(self.0))
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn try_extend_atomic() {
+        let budget = core::cell::Cell::new(0);
+        let mut vec = vec![Flaky(&budget)];
+        let other = [Flaky(&budget), Flaky(&budget), Flaky(&budget)];
+
+        budget.set(2);
+        assert!(vec.try_extend_from_slice_atomic(&other).is_err());
+        assert_eq!(vec.len(), 1);
+        budget.set(3);
+        vec.try_extend_from_slice_atomic(&other).unwrap();
+        assert_eq!(vec.len(), 4);
+
+        let mut vec = TryVec::new();
+        vec.extend_atomic(0..4).unwrap();
+        assert!(vec.extend_atomic((0..usize::MAX >> 8).map(|_| 0)).is_err());
+        assert_eq!(vec, vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn try_extend_atomic_rollback() {
+        let mut vec = Vec::new();
+        FallibleVec::try_reserve(&mut vec, 4).unwrap();
+        vec.extend(0..4);
+        let cap = vec.capacity();
+        // no lower bound, so the vec grows once and fills up again before
+        // the second growth fails
+        let iter = (4..cap as u32 * 4).filter(|_| true);
+        let res = crate::test_alloc::with_alloc_budget(1, || vec.try_extend_atomic(iter));
+        assert!(res.is_err());
+        assert_eq!(vec, [0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn try_collect() {
+        let v: Vec<_> = (0..10).try_collect().unwrap();
+        assert_eq!(v, (0..10).collect::<Vec<_>>());
+        assert!(v.capacity() >= 10);
+        let v: TryVec<_> = v.into_iter().filter(|i| i % 2 == 0).try_collect().unwrap();
+        assert_eq!(v, vec![0, 2, 4, 6, 8]);
+        assert!((0..usize::MAX).try_collect::<Vec<_>>().is_err());
+    }
+
+    #[test]
+    fn try_collect_results() {
+        let ok: Vec<Result<u32, &str>> = vec![Ok(1), Ok(2), Ok(3)];
+        let v: TryVec<u32> = ok.try_collect_results().unwrap();
+        assert_eq!(v, vec![1, 2, 3]);
+
+        let mut calls = 0;
+        let items = vec![Ok(1), Err("bad"), Ok(3)]
+            .into_iter()
+            .inspect(|_| calls += 1);
+        let err = items.try_collect_results::<Vec<u32>>().unwrap_err();
+        assert_eq!(err, CollectError::Item("bad"));
+        assert_eq!(err.to_string(), "bad");
+        assert_eq!(calls, 2);
+
+        /// a collection which never manages to allocate
+        struct Oom;
+        impl TryFromIterator<u32> for Oom {
+            fn try_from_iterator<T: IntoIterator<Item = u32>>(
+                _: T,
+            ) -> Result<Self, TryReserveError> {
+                FallibleVec::try_reserve(&mut Vec::<u8>::new(), usize::MAX).map(|_| Oom)
+            }
+        }
+        let err = vec![Ok::<u32, &str>(1)]
+            .try_collect_results::<Oom>()
+            .err()
+            .unwrap();
+        assert!(matches!(err, CollectError::Alloc(_)));
+        assert_eq!(err.to_string(), "memory allocation failed");
+
+        #[cfg(feature = "std")]
+        {
+            let err: Box<dyn std::error::Error> = Box::new(CollectError::Item(core::fmt::Error));
+            assert_eq!(err.to_string(), core::fmt::Error.to_string());
+        }
+    }
+
+    /// collection-agnostic code only needs the TryExtend bound
+    fn extend_generic<'a, C: TryExtend<u32> + TryExtend<&'a u32>>(
+        c: &mut C,
+    ) -> Result<(), TryReserveError> {
+        c.try_extend(0..2)?;
+        c.try_extend([2, 3].iter())
+    }
+
+    #[test]
+    fn try_extend() {
+        let mut v = Vec::new();
+        extend_generic(&mut v).unwrap();
+        assert_eq!(v, [0, 1, 2, 3]);
+        let mut v = TryVec::new();
+        extend_generic(&mut v).unwrap();
+        assert_eq!(v, vec![0, 1, 2, 3]);
+        let mut set = crate::btree::BTreeSet::new();
+        extend_generic(&mut set).unwrap();
+        assert!(set.iter().copied().eq(0..4));
+        let mut set = crate::TryHashSet::new();
+        extend_generic(&mut set).unwrap();
+        assert_eq!(set.len(), 4);
+    }
+
+    #[test]
+    fn tryvec_try_sort() {
+        let mut v: TryVec<(u8, u8)> = TryVec::new();
+        v.extend((0..50u8).map(|i| (i % 7, i))).unwrap();
+        v.try_sort_by_key(|p| p.0).unwrap();
+        assert!(v
+            .windows(2)
+            .all(|w| w[0].0 < w[1].0 || (w[0].0 == w[1].0 && w[0].1 < w[1].1)));
+        v.try_sort_by(|a, b| b.1.cmp(&a.1)).unwrap();
+        assert!(v.iter().map(|p| p.1).eq((0..50).rev()));
+        v.try_sort_by_cached_key(|p| p.1 % 2).unwrap();
+        assert_eq!(v[0], (6, 48));
+        v.try_sort().unwrap();
+        assert_eq!(v[0], (0, 0));
     }
 }